@@ -0,0 +1,322 @@
+//! 评论、弹幕、通知的导出与导入，便于删除前留档或离线编辑后再删除
+//!
+//! 每类项实现 [`Archived`]，导出导入的类型由调用方给出的 [`Codec`] 决定，界面中即注册的查看器
+
+use crate::http::comment::Comment;
+use crate::http::danmu::Danmu;
+use crate::http::notify::{Notify, NotifyKey};
+use crate::types::{AggregatedData, Error, Item, ItemKey, Origin, Result};
+use crate::Type;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 可导出与导入的一类项
+pub trait Archived: Item + DeserializeOwned {
+    /// 导出的 JSON 中这一类项所在的字段
+    const SECTION: &'static str;
+
+    /// 由导出文件中的 id 还原键
+    fn key(&self, id: u64) -> Self::Key;
+
+    fn to_row(&self, id: u64) -> Row;
+
+    fn from_row(row: Row) -> Result<Self>;
+}
+
+/// 一类项的读写方式，不区分具体类型
+#[derive(Clone, Copy)]
+pub struct Codec {
+    tp: Type,
+    section: &'static str,
+    to_json: fn(&AggregatedData) -> Result<Value>,
+    from_json: fn(Value, &mut AggregatedData) -> Result<()>,
+    to_rows: fn(&AggregatedData, &mut Vec<Row>),
+    from_row: fn(Row, &mut AggregatedData) -> Result<()>,
+}
+
+impl Codec {
+    pub fn of<T: Archived>() -> Self {
+        Codec {
+            tp: T::TYPE,
+            section: T::SECTION,
+            to_json: to_json::<T>,
+            from_json: from_json::<T>,
+            to_rows: to_rows::<T>,
+            from_row: from_row::<T>,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Entry<'a, T> {
+    id: u64,
+    source: Origin,
+    #[serde(flatten)]
+    item: &'a T,
+}
+
+/// 导入时忽略 source 等多余字段
+#[derive(Deserialize)]
+struct ImportedEntry<T> {
+    id: u64,
+    #[serde(flatten)]
+    item: T,
+}
+
+/// CSV 中的一行，各类项共用同一组列
+#[derive(Serialize, Deserialize)]
+pub struct Row {
+    pub kind: Type,
+    pub id: u64,
+    pub source: Origin,
+    pub oid: Option<u64>,
+    pub r#type: Option<u8>,
+    pub cid: Option<u64>,
+    pub notify_id: Option<u64>,
+    pub tp: Option<u8>,
+    pub system_notify_api: Option<u8>,
+    pub is_selected: bool,
+    /// 旧版导出的文件没有这一列
+    #[serde(default)]
+    pub time: Option<u64>,
+    #[serde(default)]
+    pub uri: Option<String>,
+    pub content: String,
+}
+
+impl Row {
+    /// 各类项共有的列，其余为空
+    fn new<T: Item>(id: u64, item: &T) -> Self {
+        Row {
+            kind: T::TYPE,
+            id,
+            source: item.origin(),
+            oid: None,
+            r#type: None,
+            cid: None,
+            notify_id: None,
+            tp: None,
+            system_notify_api: None,
+            is_selected: item.is_selected(),
+            time: item.time(),
+            uri: None,
+            content: item.content().to_string(),
+        }
+    }
+}
+
+fn missing(kind: Type, id: u64, field: &str) -> Error {
+    Error::ImportError(Arc::new(format!("{} {id} is missing `{field}`", kind.id())))
+}
+
+impl Archived for Comment {
+    const SECTION: &'static str = "comments";
+
+    fn key(&self, id: u64) -> u64 {
+        id
+    }
+
+    fn to_row(&self, id: u64) -> Row {
+        Row {
+            oid: Some(self.oid),
+            r#type: Some(self.r#type),
+            notify_id: self.notify_id,
+            tp: self.tp,
+            uri: self.uri.clone(),
+            ..Row::new(id, self)
+        }
+    }
+
+    fn from_row(row: Row) -> Result<Self> {
+        Ok(Comment {
+            oid: row.oid.ok_or_else(|| missing(row.kind, row.id, "oid"))?,
+            r#type: row
+                .r#type
+                .ok_or_else(|| missing(row.kind, row.id, "type"))?,
+            content: row.content,
+            is_selected: row.is_selected,
+            notify_id: row.notify_id,
+            tp: row.tp,
+            time: row.time,
+            from_dynamic: row.source == Origin::Dynamic,
+            uri: row.uri,
+        })
+    }
+}
+
+impl Archived for Danmu {
+    const SECTION: &'static str = "danmu";
+
+    fn key(&self, id: u64) -> u64 {
+        id
+    }
+
+    fn to_row(&self, id: u64) -> Row {
+        Row {
+            cid: Some(self.cid),
+            notify_id: self.notify_id,
+            uri: self.uri.clone(),
+            ..Row::new(id, self)
+        }
+    }
+
+    fn from_row(row: Row) -> Result<Self> {
+        Ok(Danmu {
+            content: row.content,
+            cid: row.cid.ok_or_else(|| missing(row.kind, row.id, "cid"))?,
+            is_selected: row.is_selected,
+            notify_id: row.notify_id,
+            time: row.time,
+            uri: row.uri,
+        })
+    }
+}
+
+impl Archived for Notify {
+    const SECTION: &'static str = "notify";
+
+    fn key(&self, id: u64) -> NotifyKey {
+        NotifyKey::new(self.feed(), id)
+    }
+
+    fn to_row(&self, id: u64) -> Row {
+        Row {
+            tp: Some(self.tp),
+            system_notify_api: self.system_notify_api,
+            ..Row::new(id, self)
+        }
+    }
+
+    fn from_row(row: Row) -> Result<Self> {
+        Ok(Notify {
+            content: row.content,
+            tp: row.tp.ok_or_else(|| missing(row.kind, row.id, "tp"))?,
+            is_selected: row.is_selected,
+            system_notify_api: row.system_notify_api,
+            time: row.time,
+        })
+    }
+}
+
+/// 按键排序，导出的文件便于比较
+fn sorted<T: Item>(data: &AggregatedData) -> Vec<(u64, &T)> {
+    let mut v: Vec<_> = data.get::<T>().into_iter().flatten().collect();
+    v.sort_unstable_by_key(|(key, _)| **key);
+    v.into_iter().map(|(key, item)| (key.id(), item)).collect()
+}
+
+fn to_json<T: Archived>(data: &AggregatedData) -> Result<Value> {
+    let entries: Vec<_> = sorted::<T>(data)
+        .into_iter()
+        .map(|(id, item)| Entry {
+            id,
+            source: item.origin(),
+            item,
+        })
+        .collect();
+    Ok(serde_json::to_value(entries)?)
+}
+
+fn from_json<T: Archived>(value: Value, data: &mut AggregatedData) -> Result<()> {
+    let entries: Vec<ImportedEntry<T>> = serde_json::from_value(value)?;
+    data.entry::<T>()
+        .extend(entries.into_iter().map(|e| (e.item.key(e.id), e.item)));
+    Ok(())
+}
+
+fn to_rows<T: Archived>(data: &AggregatedData, rows: &mut Vec<Row>) {
+    rows.extend(
+        sorted::<T>(data)
+            .into_iter()
+            .map(|(id, item)| item.to_row(id)),
+    );
+}
+
+fn from_row<T: Archived>(row: Row, data: &mut AggregatedData) -> Result<()> {
+    let id = row.id;
+    let item = T::from_row(row)?;
+    data.entry::<T>().insert(item.key(id), item);
+    Ok(())
+}
+
+/// 根据扩展名导出为 CSV 或 JSON（默认），只导出 `codecs` 中的类型
+pub fn export(path: &Path, data: &AggregatedData, codecs: &[Codec]) -> Result<()> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        export_csv(path, data, codecs)
+    } else {
+        export_json(path, data, codecs)
+    }
+}
+
+fn export_json(path: &Path, data: &AggregatedData, codecs: &[Codec]) -> Result<()> {
+    let mut archive = Map::new();
+    for codec in codecs {
+        archive.insert(codec.section.to_string(), (codec.to_json)(data)?);
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &archive)?;
+    Ok(())
+}
+
+fn export_csv(path: &Path, data: &AggregatedData, codecs: &[Codec]) -> Result<()> {
+    let mut rows = Vec::new();
+    for codec in codecs {
+        (codec.to_rows)(data, &mut rows);
+    }
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 导入由 [`export`] 导出（可能经过编辑）的文件，忽略 `codecs` 以外的 JSON 字段
+pub fn import(path: &Path, codecs: &[Codec]) -> Result<AggregatedData> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        import_csv(path, codecs)
+    } else {
+        import_json(path, codecs)
+    }
+}
+
+fn import_json(path: &Path, codecs: &[Codec]) -> Result<AggregatedData> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut archive: Map<String, Value> = serde_json::from_reader(reader)?;
+    let mut data = AggregatedData::default();
+    for codec in codecs {
+        if let Some(section) = archive.remove(codec.section) {
+            (codec.from_json)(section, &mut data)?;
+        }
+    }
+    Ok(data)
+}
+
+fn import_csv(path: &Path, codecs: &[Codec]) -> Result<AggregatedData> {
+    let codecs: HashMap<Type, &Codec> = codecs.iter().map(|c| (c.tp, c)).collect();
+    let mut data = AggregatedData::default();
+    let mut reader = csv::Reader::from_path(path)?;
+    for row in reader.deserialize() {
+        let row: Row = row?;
+        let Some(codec) = codecs.get(&row.kind) else {
+            return Err(Error::ImportError(Arc::new(format!(
+                "{} is not supported",
+                row.kind.id()
+            ))));
+        };
+        (codec.from_row)(row, &mut data)?;
+    }
+    Ok(data)
+}
@@ -0,0 +1,344 @@
+use bilibili_comment_cleaning::archive::Codec;
+use bilibili_comment_cleaning::filter::{Filter, FilterMode, Filterable};
+use bilibili_comment_cleaning::http::api_service::ApiService;
+use bilibili_comment_cleaning::http::comment::Comment;
+use bilibili_comment_cleaning::http::danmu::Danmu;
+use bilibili_comment_cleaning::http::notify::{Notify, NotifyKey};
+use bilibili_comment_cleaning::http::rate_limit::{Rate, RateLimits};
+use bilibili_comment_cleaning::http::source::{self, Registry};
+use bilibili_comment_cleaning::{
+    archive, dry_run, handle_delete, retry::RetryPolicy, screens::main, types::*, Type, ViewerMsg,
+};
+use clap::Parser;
+use iced::futures::channel::mpsc;
+use iced::futures::StreamExt;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+use tracing_subscriber::fmt::time::LocalTime;
+
+/// 无界面模式：获取并删除历史评论、弹幕、通知
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// Cookie 字符串（需包含 SESSDATA、bili_jct、DedeUserID）
+    #[arg(
+        long,
+        conflicts_with = "cookie_file",
+        required_unless_present = "cookie_file"
+    )]
+    cookie: Option<String>,
+    /// 从文件读取 Cookie，支持请求头、Copy as cURL、cookies.txt 与 JSON 导出
+    #[arg(long)]
+    cookie_file: Option<PathBuf>,
+    /// 同时从 aicu.cc 获取评论和弹幕
+    #[arg(long)]
+    aicu: bool,
+    /// 删除评论
+    #[arg(long)]
+    comments: bool,
+    /// 删除弹幕
+    #[arg(long)]
+    danmu: bool,
+    /// 删除通知
+    #[arg(long)]
+    notify: bool,
+    /// bilibili 接口每秒的请求数，遇到风控时会自动降低
+    #[arg(long, default_value_t = 2.0)]
+    rate: f64,
+    /// aicu.cc 接口每秒的请求数
+    #[arg(long, default_value_t = 1.0 / 3.0)]
+    aicu_rate: f64,
+    /// 每一项删除失败（网络错误、风控）时最多尝试的次数
+    #[arg(long, default_value_t = RetryPolicy::default().max_attempts)]
+    delete_attempts: u32,
+    /// 获取被中断时最多尝试的次数，每次从中断处继续
+    #[arg(long, default_value_t = 10)]
+    fetch_attempts: u32,
+    /// 删除失败后第一次重试前的等待时间（秒），之后每次翻倍
+    #[arg(long, default_value_t = RetryPolicy::default().backoff, value_parser = seconds)]
+    backoff: f32,
+    /// 删除重试等待时间上限（秒）
+    #[arg(long, default_value_t = RetryPolicy::default().max_backoff, value_parser = seconds)]
+    max_backoff: f32,
+    /// 获取被中断后重试前的等待时间（秒）
+    #[arg(long, default_value_t = 10)]
+    retry_after: u64,
+    /// 删除前将获取到的数据导出到文件（.json 或 .csv）
+    #[arg(long)]
+    export: Option<PathBuf>,
+    /// 从导出的文件中读取待删除项，跳过获取
+    #[arg(long, conflicts_with = "aicu")]
+    import: Option<PathBuf>,
+    /// 只删除包含该关键词的项
+    #[arg(long)]
+    contains: Option<String>,
+    /// 只删除匹配该正则表达式的项
+    #[arg(long)]
+    regex: Option<String>,
+    /// 只删除字数不少于该值的项
+    #[arg(long)]
+    min_len: Option<usize>,
+    /// 只删除字数不多于该值的项
+    #[arg(long)]
+    max_len: Option<usize>,
+    /// 只删除早于该时间的项，如 2023-01-01 或 "2023-01-01 12:00"
+    #[arg(long)]
+    before: Option<String>,
+    /// 只删除不早于该时间的项
+    #[arg(long)]
+    after: Option<String>,
+    /// 不删除，只生成将要发送的请求的报告
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Args {
+    /// 未指定任何类型时删除全部
+    fn delete_all(&self) -> bool {
+        !(self.comments || self.danmu || self.notify)
+    }
+
+    fn rate_limits(&self) -> RateLimits {
+        let rate = |per_second: f64| (per_second > 0.0).then(|| Rate::per_second(per_second));
+        RateLimits {
+            api: rate(self.rate),
+            message: rate(self.rate),
+            aicu: rate(self.aicu_rate),
+            // 删除请求同样受 --rate 限制
+            delete: None,
+        }
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.delete_attempts.max(1),
+            backoff: self.backoff,
+            max_backoff: self.max_backoff,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// 需要同时满足的筛选条件
+    fn filters(&self) -> Result<Vec<Filter>> {
+        let mut filters = Vec::new();
+        if let Some(s) = &self.contains {
+            filters.push(Filter::new(FilterMode::Contains, s)?);
+        }
+        if let Some(s) = &self.regex {
+            filters.push(Filter::new(FilterMode::Regex, s)?);
+        }
+        if let Some(n) = self.min_len {
+            filters.push(Filter::MinLength(n));
+        }
+        if let Some(n) = self.max_len {
+            filters.push(Filter::MaxLength(n));
+        }
+        if let Some(s) = &self.before {
+            filters.push(Filter::new(FilterMode::Before, s)?);
+        }
+        if let Some(s) = &self.after {
+            filters.push(Filter::new(FilterMode::After, s)?);
+        }
+        Ok(filters)
+    }
+}
+
+/// 满足全部筛选条件
+/// 不小于 0 的有限秒数
+fn seconds(s: &str) -> std::result::Result<f32, String> {
+    let secs: f32 = s.parse().map_err(|e| e.to_string())?;
+    if secs.is_finite() && secs >= 0.0 {
+        Ok(secs)
+    } else {
+        Err(format!(
+            "expected a non-negative number of seconds, got `{s}`"
+        ))
+    }
+}
+
+fn wanted<T: Filterable>(filters: &[Filter], item: &T) -> bool {
+    filters.iter().all(|f| f.matches(item))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .compact()
+        .with_target(false)
+        .with_timer(LocalTime::rfc_3339())
+        .init();
+
+    let args = Args::parse();
+    let cookie = match (&args.cookie, &args.cookie_file) {
+        (Some(c), _) => c.clone(),
+        (None, Some(path)) => std::fs::read_to_string(path)?.trim().to_string(),
+        (None, None) => unreachable!(),
+    };
+    let filters = args.filters()?;
+    let api = Arc::new(ApiService::new(cookie)?.with_rate_limits(args.rate_limits()));
+
+    let codecs = [
+        Codec::of::<Comment>(),
+        Codec::of::<Danmu>(),
+        Codec::of::<Notify>(),
+    ];
+    let mut data = if let Some(path) = &args.import {
+        archive::import(path, &codecs)?
+    } else {
+        let sources = Registry::default().with_aicu(args.aicu);
+        let mut progress = FetchProgressState::default();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match source::fetch(api.clone(), sources.clone(), progress, |_, _| {}).await? {
+                (Some(data), _) => break Arc::unwrap_or_clone(data),
+                (None, Some(_)) if attempts >= args.fetch_attempts => {
+                    return Err(Error::FetchInterrupted(attempts));
+                }
+                (None, Some(p)) => {
+                    warn!(
+                        "Fetching interrupted, retrying in {} seconds.",
+                        args.retry_after
+                    );
+                    progress = p;
+                    sleep(Duration::from_secs(args.retry_after)).await;
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    };
+    if let Some(path) = &args.export {
+        archive::export(path, &data, &codecs)?;
+        info!("Exported to {}", path.display());
+    }
+    let (comments, danmu, notify) = (
+        data.take::<Comment>(),
+        data.take::<Danmu>(),
+        data.take::<Notify>(),
+    );
+    info!(
+        "Fetched {} comments, {} danmu, {} notify.",
+        comments.len(),
+        danmu.len(),
+        notify.len()
+    );
+
+    let delete_flag = Arc::new(AtomicBool::new(true));
+    // handle_delete 停止后会复位 delete_flag，需单独记录是否已被中断
+    let stopped = Arc::new(AtomicBool::new(false));
+    {
+        let delete_flag = delete_flag.clone();
+        let stopped = stopped.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                warn!("Stopping after the current request...");
+                stopped.store(true, Ordering::SeqCst);
+                delete_flag.store(false, Ordering::SeqCst);
+            }
+        });
+    }
+
+    // 记录已删除的评论，随评论一起删除的通知不再重复删除
+    let deleted_comments = Arc::new(Mutex::new(HashSet::new()));
+    let (output, mut receiver) = mpsc::channel(100);
+    let drain = {
+        let deleted_comments = deleted_comments.clone();
+        tokio::spawn(async move {
+            let mut failed = 0usize;
+            while let Some(msg) = receiver.next().await {
+                let Message::Main(main::Message::Viewer(msg)) = msg else {
+                    continue;
+                };
+                if msg.is_delete_failed() {
+                    failed += 1;
+                } else if let Some(ViewerMsg::Deleted { key }) = msg.downcast::<Comment>() {
+                    deleted_comments.lock().unwrap().insert(key);
+                }
+            }
+            failed
+        })
+    };
+
+    if args.delete_all() || args.comments {
+        let items: Vec<_> = comments
+            .iter()
+            .filter(|(_, c)| c.is_selected && wanted(&filters, *c))
+            .map(|(id, c)| (*id, c.clone()))
+            .collect();
+        if args.dry_run {
+            info!("{}", dry_run::run(&items, Type::Comment)?);
+            // 演练时视为已删除，通知部分不再重复列出
+            deleted_comments
+                .lock()
+                .unwrap()
+                .extend(items.iter().map(|(id, _)| *id));
+        } else {
+            handle_delete(
+                delete_flag.clone(),
+                output.clone(),
+                items,
+                args.retry_policy(),
+                api.clone(),
+            )
+            .await;
+        }
+    }
+    if (args.delete_all() || args.danmu) && !stopped.load(Ordering::SeqCst) {
+        let items: Vec<_> = danmu
+            .iter()
+            .filter(|(_, d)| d.is_selected && wanted(&filters, *d))
+            .map(|(id, d)| (*id, d.clone()))
+            .collect();
+        if args.dry_run {
+            info!("{}", dry_run::run(&items, Type::Danmu)?);
+        } else {
+            handle_delete(
+                delete_flag.clone(),
+                output.clone(),
+                items,
+                args.retry_policy(),
+                api.clone(),
+            )
+            .await;
+        }
+    }
+    if (args.delete_all() || args.notify) && !stopped.load(Ordering::SeqCst) {
+        let removed: HashSet<NotifyKey> = deleted_comments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|rpid| comments.get(rpid).and_then(Comment::notify_key))
+            .collect();
+        let items: Vec<_> = notify
+            .iter()
+            .filter(|(id, n)| n.is_selected && wanted(&filters, *n) && !removed.contains(id))
+            .map(|(id, n)| (*id, n.clone()))
+            .collect();
+        if args.dry_run {
+            info!("{}", dry_run::run(&items, Type::Notify)?);
+        } else {
+            handle_delete(
+                delete_flag.clone(),
+                output.clone(),
+                items,
+                args.retry_policy(),
+                api.clone(),
+            )
+            .await;
+        }
+    }
+
+    drop(output);
+    if let Ok(failed @ 1..) = drain.await {
+        warn!(
+            "{} items failed to delete, see journal.jsonl for details.",
+            failed
+        );
+    }
+    Ok(())
+}
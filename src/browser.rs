@@ -0,0 +1,295 @@
+//! 从本机浏览器的配置中读取 bilibili 的 Cookie
+//!
+//! Firefox 的 Cookie 未加密；Chromium 系只支持 Linux 上未使用系统钥匙串时的 “peanuts” 密钥（v10）
+
+use crate::http::cookie::{is_bilibili_host, CookieJar};
+use crate::types::{Error, Result};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use rusqlite::Connection;
+use sha1::Sha1;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chromium,
+}
+
+/// 浏览器的一个配置（用户）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub browser: Browser,
+    pub name: String,
+    /// Cookie 数据库
+    pub cookies: PathBuf,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// 本机能找到 Cookie 数据库的浏览器配置
+pub fn profiles() -> Vec<Profile> {
+    let mut profiles = firefox_profiles();
+    profiles.extend(chromium_profiles());
+    profiles
+}
+
+fn firefox_profiles() -> Vec<Profile> {
+    let mut roots = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".mozilla/firefox"));
+        roots.push(home.join("snap/firefox/common/.mozilla/firefox"));
+        roots.push(home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"));
+    }
+    if let Some(config) = dirs::config_dir() {
+        // macOS 与 Windows
+        roots.push(config.join("Firefox/Profiles"));
+        roots.push(config.join("Mozilla/Firefox/Profiles"));
+    }
+
+    let mut profiles = Vec::new();
+    for root in roots {
+        for dir in sub_dirs(&root) {
+            let cookies = dir.join("cookies.sqlite");
+            if cookies.is_file() {
+                // 目录名形如 `xxxxxxxx.default-release`
+                let dir_name = file_name(&dir);
+                let name = dir_name
+                    .split_once('.')
+                    .map_or(dir_name.as_str(), |(_, n)| n);
+                profiles.push(Profile {
+                    browser: Browser::Firefox,
+                    name: format!("Firefox ({name})"),
+                    cookies,
+                });
+            }
+        }
+    }
+    profiles
+}
+
+#[cfg(target_os = "linux")]
+fn chromium_profiles() -> Vec<Profile> {
+    const BROWSERS: [(&str, &str); 6] = [
+        ("chromium", "Chromium"),
+        ("google-chrome", "Google Chrome"),
+        ("google-chrome-beta", "Google Chrome Beta"),
+        ("BraveSoftware/Brave-Browser", "Brave"),
+        ("microsoft-edge", "Microsoft Edge"),
+        ("vivaldi", "Vivaldi"),
+    ];
+    let Some(config) = dirs::config_dir() else {
+        return Vec::new();
+    };
+
+    let mut profiles = Vec::new();
+    for (dir, browser) in BROWSERS {
+        for profile in sub_dirs(&config.join(dir)) {
+            let name = file_name(&profile);
+            if name != "Default" && !name.starts_with("Profile ") {
+                continue;
+            }
+            let cookies = [profile.join("Network/Cookies"), profile.join("Cookies")]
+                .into_iter()
+                .find(|p| p.is_file());
+            if let Some(cookies) = cookies {
+                profiles.push(Profile {
+                    browser: Browser::Chromium,
+                    name: format!("{browser} ({name})"),
+                    cookies,
+                });
+            }
+        }
+    }
+    profiles
+}
+
+/// 其他系统上 Chromium 使用系统的密钥加密 Cookie，无法读取
+#[cfg(not(target_os = "linux"))]
+fn chromium_profiles() -> Vec<Profile> {
+    Vec::new()
+}
+
+/// 读取配置中 bilibili.com 的 Cookie，拼接为请求头的格式
+pub fn read_cookie(profile: &Profile) -> Result<String> {
+    // 浏览器运行时会锁住数据库，复制一份再读
+    let dir = PrivateDir::create()?;
+    let copy = dir.0.join("cookies.sqlite");
+    let wal = |p: &Path| PathBuf::from(format!("{}-wal", p.display()));
+    copy_private(&profile.cookies, &copy)?;
+    if wal(&profile.cookies).is_file() {
+        copy_private(&wal(&profile.cookies), &wal(&copy))?;
+    }
+
+    let conn = Connection::open(&copy).map_err(browser_error)?;
+    let jar = match profile.browser {
+        Browser::Firefox => read_firefox(&conn),
+        Browser::Chromium => read_chromium(&conn),
+    }?;
+    Ok(jar.to_string())
+}
+
+/// 只有当前用户可访问的临时目录，离开作用域时连同其中的副本一起删除
+struct PrivateDir(PathBuf);
+
+impl PrivateDir {
+    fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "bilibili-comment-cleaning-cookies-{}-{:x}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        // 已存在时失败，不会使用他人预先创建的目录
+        builder.create(&path)?;
+        Ok(PrivateDir(path))
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 复制为权限 0600 的新文件
+fn copy_private(from: &Path, to: &Path) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(to)?;
+    std::io::copy(&mut fs::File::open(from)?, &mut file)?;
+    Ok(())
+}
+
+/// 子域名的 Cookie 在前，`.bilibili.com` 的覆盖同名的
+fn read_firefox(conn: &Connection) -> Result<CookieJar> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT host, name, value FROM moz_cookies \
+             WHERE host LIKE '%bilibili.com' ORDER BY length(host) DESC",
+        )
+        .map_err(browser_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(browser_error)?;
+
+    let mut jar = CookieJar::default();
+    for row in rows {
+        let (host, name, value) = row.map_err(browser_error)?;
+        // LIKE 只用于缩小范围，域名以 is_bilibili_host 为准
+        if is_bilibili_host(&host) {
+            jar.set(&name, &value);
+        }
+    }
+    Ok(jar)
+}
+
+fn read_chromium(conn: &Connection) -> Result<CookieJar> {
+    let version: i64 = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(browser_error)?
+        .parse()
+        .unwrap_or_default();
+    let mut stmt = conn
+        .prepare(
+            "SELECT host_key, name, value, encrypted_value FROM cookies \
+             WHERE host_key LIKE '%bilibili.com' ORDER BY length(host_key) DESC",
+        )
+        .map_err(browser_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+            ))
+        })
+        .map_err(browser_error)?;
+
+    let mut jar = CookieJar::default();
+    for row in rows {
+        let (host, name, value, encrypted) = row.map_err(browser_error)?;
+        if !is_bilibili_host(&host) {
+            continue;
+        }
+        let value = if encrypted.is_empty() {
+            value
+        } else {
+            decrypt_chromium(&encrypted, version)
+                .map_err(|e| browser_error(format!("{name}: {e}")))?
+        };
+        jar.set(&name, &value);
+    }
+    Ok(jar)
+}
+
+/// Linux 上未使用系统钥匙串时，Chromium 用固定的密码 “peanuts” 加密
+fn decrypt_chromium(encrypted: &[u8], db_version: i64) -> std::result::Result<String, String> {
+    let Some(data) = encrypted.strip_prefix(b"v10") else {
+        return Err("encrypted with the system keyring, which is not supported".to_string());
+    };
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+    let iv = [b' '; 16];
+    let mut buf = data.to_vec();
+    let plain = Aes128CbcDec::new(&key.into(), &iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(|_| "failed to decrypt".to_string())?;
+    // 数据库版本 24 起，值前面加上了域名的 SHA256
+    let plain = if db_version >= 24 {
+        plain.get(32..).unwrap_or_default()
+    } else {
+        plain
+    };
+    String::from_utf8(plain.to_vec()).map_err(|e| e.to_string())
+}
+
+fn browser_error(e: impl ToString) -> Error {
+    Error::BrowserCookieError(Arc::new(e.to_string()))
+}
+
+fn sub_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<_> = entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
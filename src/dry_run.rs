@@ -0,0 +1,93 @@
+//! 演练模式：列出删除时会发送的请求并生成报告，不会删除任何东西
+
+use crate::filter::Filterable;
+use crate::http::notify::Feed;
+use crate::storage::{data_dir, write_atomic};
+use crate::types::{ItemKey, PlannedRequest, RemoveAble, Result};
+use crate::Type;
+use chrono::Local;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+
+/// 报告中以此代替真实的 csrf，避免泄露登录状态
+const CSRF_PLACEHOLDER: &str = "<csrf>";
+
+#[derive(Serialize)]
+struct PlannedItem<'a> {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feed: Option<Feed>,
+    content: &'a str,
+    time: Option<u64>,
+    requests: Vec<PlannedRequest>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    kind: String,
+    generated_at: String,
+    request_count: usize,
+    items: Vec<PlannedItem<'a>>,
+}
+
+/// 演练结果摘要
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub tp: Type,
+    pub items: usize,
+    pub requests: usize,
+    pub report: PathBuf,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[dry run] 将删除{} {} 项，共 {} 个请求，报告：{}",
+            self.tp,
+            self.items,
+            self.requests,
+            self.report.display()
+        )
+    }
+}
+
+/// 生成报告并保存到数据目录下的 reports
+pub fn run<T: RemoveAble + Filterable>(items: &[(T::Key, T)], tp: Type) -> Result<Summary> {
+    let items: Vec<_> = items
+        .iter()
+        .map(|(key, item)| PlannedItem {
+            id: key.id(),
+            feed: key.feed(),
+            content: item.content(),
+            time: item.time(),
+            requests: item.plan(*key, CSRF_PLACEHOLDER),
+        })
+        .collect();
+    let request_count = items.iter().map(|i| i.requests.len()).sum();
+    let now = Local::now();
+    let report = Report {
+        kind: tp.to_string(),
+        generated_at: now.to_rfc3339(),
+        request_count,
+        items,
+    };
+
+    let dir = data_dir()?.join("reports");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "dry-run-{}-{}.json",
+        tp.id(),
+        now.format("%Y%m%d-%H%M%S")
+    ));
+    write_atomic(&path, &serde_json::to_vec_pretty(&report)?)?;
+
+    Ok(Summary {
+        tp,
+        items: report.items.len(),
+        requests: request_count,
+        report: path,
+    })
+}
@@ -0,0 +1,171 @@
+//! 按内容或时间批量选择 / 取消选择待删除项
+
+use crate::types::{Error, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// 筛选方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// 包含关键词
+    #[default]
+    Contains,
+    /// 匹配正则表达式
+    Regex,
+    /// 字数不少于
+    MinLength,
+    /// 字数不多于
+    MaxLength,
+    /// 早于某个时间
+    Before,
+    /// 不早于某个时间
+    After,
+    /// 介于两个时间之间，如 `2023-01-01..2023-06-30`
+    Between,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 7] = [
+        FilterMode::Contains,
+        FilterMode::Regex,
+        FilterMode::MinLength,
+        FilterMode::MaxLength,
+        FilterMode::Before,
+        FilterMode::After,
+        FilterMode::Between,
+    ];
+}
+
+impl Display for FilterMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterMode::Contains => write!(f, "contains"),
+            FilterMode::Regex => write!(f, "regex"),
+            FilterMode::MinLength => write!(f, "length ≥"),
+            FilterMode::MaxLength => write!(f, "length ≤"),
+            FilterMode::Before => write!(f, "before"),
+            FilterMode::After => write!(f, "after"),
+            FilterMode::Between => write!(f, "between"),
+        }
+    }
+}
+
+/// 可被筛选的项
+pub trait Filterable {
+    fn content(&self) -> &str;
+    /// Unix 时间戳（秒），未知时为 `None`
+    fn time(&self) -> Option<u64>;
+}
+
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Contains(String),
+    Regex(Regex),
+    MinLength(usize),
+    MaxLength(usize),
+    /// 时间早于该时间戳
+    Before(u64),
+    /// 时间不早于该时间戳
+    After(u64),
+    /// [开始, 结束)
+    Between(u64, u64),
+}
+
+impl Filter {
+    /// 根据筛选方式解析输入，正则、数字或日期无效时返回错误
+    pub fn new(mode: FilterMode, input: &str) -> Result<Self> {
+        let length = || {
+            input
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| Error::InvalidFilter(Arc::new(e.to_string())))
+        };
+        Ok(match mode {
+            FilterMode::Contains => Filter::Contains(input.to_string()),
+            FilterMode::Regex => Filter::Regex(
+                Regex::new(input).map_err(|e| Error::InvalidFilter(Arc::new(e.to_string())))?,
+            ),
+            FilterMode::MinLength => Filter::MinLength(length()?),
+            FilterMode::MaxLength => Filter::MaxLength(length()?),
+            FilterMode::Before => Filter::Before(parse_time(input, false)?),
+            FilterMode::After => Filter::After(parse_time(input, false)?),
+            FilterMode::Between => {
+                let (start, end) = input.split_once("..").ok_or_else(|| {
+                    Error::InvalidFilter(Arc::new(format!("expected `start..end`, got `{input}`")))
+                })?;
+                Filter::Between(parse_time(start, false)?, parse_time(end, true)?)
+            }
+        })
+    }
+
+    /// 字数按字符计算，没有时间的项不匹配任何时间条件
+    pub fn matches<T: Filterable>(&self, item: &T) -> bool {
+        let content = item.content();
+        match self {
+            Filter::Contains(s) => content.contains(s.as_str()),
+            Filter::Regex(re) => re.is_match(content),
+            Filter::MinLength(n) => content.chars().count() >= *n,
+            Filter::MaxLength(n) => content.chars().count() <= *n,
+            Filter::Before(t) => item.time().is_some_and(|time| time < *t),
+            Filter::After(t) => item.time().is_some_and(|time| time >= *t),
+            Filter::Between(start, end) => item
+                .time()
+                .is_some_and(|time| (*start..*end).contains(&time)),
+        }
+    }
+}
+
+/// 解析本地时间 `2023-01-01` / `2023-01-01 12:00` / `2023-01-01 12:00:00`。
+/// 只有日期时 `end_of_day` 为 true 则取第二天零点，用作区间的结束
+pub fn parse_time(input: &str, end_of_day: bool) -> Result<u64> {
+    let input = input.trim();
+    let invalid = || Error::InvalidFilter(Arc::new(format!("invalid date `{input}`")));
+    let datetime = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(input, "%Y-%m-%d").ok()?;
+            let date = if end_of_day { date.succ_opt()? } else { date };
+            date.and_hms_opt(0, 0, 0)
+        })
+        .ok_or_else(invalid)?;
+    let local = Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .ok_or_else(invalid)?;
+    u64::try_from(local.timestamp()).map_err(|_| invalid())
+}
+
+/// 以本地时间显示时间戳
+pub fn format_time(time: u64) -> String {
+    i64::try_from(time)
+        .ok()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// 对匹配项的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// 选择匹配项
+    Select,
+    /// 取消选择匹配项
+    Deselect,
+    /// 只选择匹配项，其余取消选择
+    Only,
+}
+
+impl FilterAction {
+    /// 返回应用后的选择状态，`None` 表示不变
+    pub fn apply(self, matched: bool) -> Option<bool> {
+        match (self, matched) {
+            (FilterAction::Select, true) => Some(true),
+            (FilterAction::Deselect, true) => Some(false),
+            (FilterAction::Only, m) => Some(m),
+            (_, false) => None,
+        }
+    }
+}
@@ -1,22 +1,146 @@
 use crate::{
-    types::{Error, Result},
+    http::{
+        cookie::CookieJar,
+        rate_limit::{Limiter, Rate, RateLimits, TokenBucket},
+        response::official::{member_account, Envelope},
+    },
+    storage::{self, AccountStore},
+    types::{BiliApiError, CookieError, Error, PlannedRequest, Result, Session},
     UA,
 };
-use reqwest::{header, Client, IntoUrl, Response};
-use serde::de::DeserializeOwned;
+use reqwest::{header, Client, IntoUrl, RequestBuilder, Response, StatusCode};
+use rsa::{pkcs8::DecodePublicKey, rand_core::OsRng, sha2::Sha256, Oaep, RsaPublicKey};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
 use serde_json::Value;
-use tracing::debug;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
 
+const API: &str = "https://api.bilibili.com";
+const MESSAGE: &str = "https://message.bilibili.com";
+const PASSPORT: &str = "https://passport.bilibili.com";
+const WWW: &str = "https://www.bilibili.com";
+const AICU: &str = "https://api.aicu.cc";
+
+/// 账号未登录
+const NOT_LOGGED_IN: i64 = -101;
+
+/// 生成 correspondPath 用的 bilibili 公钥
+const REFRESH_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDLgd2OAkcGVtoE3ThUREbio0Eg
+Uc/prcajMKXvkCKFCWhJYJcLkcM2DKKcSeFpD/j6Boy538YXnR6VhcuUJOhH2x71
+nzPjfdTcqMz7djHum0qSZA0AyCBDABUqCrfNgCiJ00Ra7GmRj+YCK1NJEuewlb40
+JNrRuoEUXpabUzGB8QIDAQAB
+-----END PUBLIC KEY-----";
+
+/// bilibili 与 aicu.cc 的接口地址，测试时可指向本地服务
+#[derive(Debug, Clone)]
+pub struct BaseUrls {
+    pub api: String,
+    pub message: String,
+    pub passport: String,
+    pub www: String,
+    pub aicu: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self {
+            api: API.to_string(),
+            message: MESSAGE.to_string(),
+            passport: PASSPORT.to_string(),
+            www: WWW.to_string(),
+            aicu: AICU.to_string(),
+        }
+    }
+}
+
+impl BaseUrls {
+    /// 所有接口都指向同一个地址
+    pub fn all(base: &str) -> Self {
+        Self {
+            api: base.to_string(),
+            message: base.to_string(),
+            passport: base.to_string(),
+            www: base.to_string(),
+            aicu: base.to_string(),
+        }
+    }
+
+    /// 将默认地址替换为配置的地址
+    pub fn resolve(&self, url: &str) -> String {
+        [
+            (API, &self.api),
+            (MESSAGE, &self.message),
+            (PASSPORT, &self.passport),
+            (WWW, &self.www),
+            (AICU, &self.aicu),
+        ]
+        .into_iter()
+        .find_map(|(default, base)| {
+            url.strip_prefix(default)
+                .map(|rest| format!("{}{}", base.trim_end_matches('/'), rest))
+        })
+        .unwrap_or_else(|| url.to_string())
+    }
+}
+
+/// 当前使用的登录凭据，刷新 Cookie 后整体替换
 #[derive(Debug)]
-pub struct ApiService {
+struct Credentials {
     client: Client,
     csrf: String,
+    session: Session,
+}
+
+impl Credentials {
+    /// 解析并检查 Cookie，保存的 Cookie 统一为请求头的格式
+    fn new(mut session: Session) -> Result<Self> {
+        let jar = CookieJar::parse(&session.cookie).map_err(Error::InvalidCookie)?;
+        let csrf = jar.csrf().to_string();
+        session.cookie = jar.to_string();
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::USER_AGENT, header::HeaderValue::from_static(UA));
+        headers.insert(
+            header::COOKIE,
+            header::HeaderValue::from_str(&session.cookie)
+                .map_err(|_| Error::InvalidCookie(CookieError::NonAscii("Cookie".into())))?,
+        );
+        let client = Client::builder()
+            .default_headers(headers)
+            .cookie_store(true)
+            .use_rustls_tls()
+            .build()
+            .unwrap();
+
+        Ok(Self {
+            client,
+            csrf,
+            session,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ApiService {
+    credentials: RwLock<Credentials>,
+    /// 同时失效的多个请求只刷新一次
+    refresh_lock: tokio::sync::Mutex<()>,
+    /// 刷新后的 Cookie 写回保存的账号
+    account_store: Option<AccountStore>,
+    base_urls: BaseUrls,
+    /// 所有请求共用的限速
+    limiter: Limiter,
+    /// 删除记录与获取进度的目录，为 None 时使用 [`storage::data_dir`]
+    data_dir: Option<PathBuf>,
 }
 
 impl Default for ApiService {
     fn default() -> Self {
-        Self {
-            client: Client::builder()
+        Self::new_with_fields(
+            Client::builder()
                 .default_headers({
                     let mut headers = header::HeaderMap::new();
                     headers.insert("User-Agent", header::HeaderValue::from_static(UA));
@@ -26,68 +150,197 @@ impl Default for ApiService {
                 .use_rustls_tls()
                 .build()
                 .unwrap(),
-            csrf: String::new(),
-        }
+            String::new(),
+        )
     }
 }
 
 impl ApiService {
     pub fn new(ck: String) -> Result<Self> {
-        let a = ck.find("bili_jct=").ok_or(Error::CreateApiServiceError)?;
-        let b = ck[a..].find(";").unwrap();
-        let csrf = ck[a + 9..b + a].to_string();
-
-        let mut headers = header::HeaderMap::new();
-        headers.insert(header::USER_AGENT, header::HeaderValue::from_static(UA));
-        headers.insert(header::COOKIE, header::HeaderValue::from_str(&ck).unwrap());
-        let client = Client::builder()
-            .default_headers(headers)
-            .cookie_store(true)
-            .use_rustls_tls()
-            .build()
-            .unwrap();
+        Self::from_session(Session {
+            cookie: ck,
+            refresh_token: None,
+        })
+    }
 
-        Ok(Self { client, csrf })
+    pub fn from_session(session: Session) -> Result<Self> {
+        Ok(Self {
+            credentials: RwLock::new(Credentials::new(session)?),
+            refresh_lock: Default::default(),
+            account_store: None,
+            base_urls: BaseUrls::default(),
+            limiter: RateLimits::default().into(),
+            data_dir: None,
+        })
     }
 
     pub fn new_with_fields(client: Client, csrf: String) -> Self {
-        Self { client, csrf }
+        Self {
+            credentials: RwLock::new(Credentials {
+                client,
+                csrf,
+                session: Session::default(),
+            }),
+            refresh_lock: Default::default(),
+            account_store: None,
+            base_urls: BaseUrls::default(),
+            limiter: RateLimits::default().into(),
+            data_dir: None,
+        }
+    }
+
+    // 替换接口地址
+    pub fn with_base_urls(mut self, base_urls: BaseUrls) -> Self {
+        self.base_urls = base_urls;
+        self
+    }
+
+    // 替换各域名的限速
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.limiter = limits.into();
+        self
+    }
+
+    // 删除记录与获取进度写入指定的目录
+    pub fn with_data_dir(mut self, dir: PathBuf) -> Self {
+        self.data_dir = Some(dir);
+        self
+    }
+
+    // 删除记录与获取进度所在的目录
+    pub fn data_dir(&self) -> Result<PathBuf> {
+        match &self.data_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                Ok(dir.clone())
+            }
+            None => storage::data_dir(),
+        }
+    }
+
+    // 调整删除请求的限速，界面中可随时修改
+    pub fn set_delete_rate(&self, rate: Option<Rate>) {
+        self.limiter.set_delete(rate);
+    }
+
+    // 刷新 Cookie 后同时更新保存的账号
+    pub fn with_account_store(mut self, store: AccountStore) -> Self {
+        self.account_store = Some(store);
+        self
+    }
+
+    // 按配置的接口地址改写请求地址
+    fn url(&self, url: impl IntoUrl) -> Result<String> {
+        Ok(self.base_urls.resolve(url.into_url()?.as_str()))
+    }
+
+    // 请求地址对应的令牌桶，登录相关的域名不限速
+    fn bucket(&self, url: &str) -> Option<&TokenBucket> {
+        let url = self.base_urls.resolve(url);
+        [
+            (&self.base_urls.api, &self.limiter.api),
+            (&self.base_urls.message, &self.limiter.message),
+            (&self.base_urls.aicu, &self.limiter.aicu),
+        ]
+        .into_iter()
+        .find(|(base, _)| url.starts_with(base.as_str()))
+        .and_then(|(_, bucket)| bucket.as_ref())
+    }
+
+    // 限速后发送请求，HTTP 412 视为风控
+    async fn execute(&self, url: &str, request: RequestBuilder) -> Result<Response> {
+        let bucket = self.bucket(url);
+        if let Some(bucket) = bucket {
+            bucket.acquire().await;
+        }
+        let res = request.send().await?;
+        if let Some(bucket) = bucket.filter(|_| res.status() == StatusCode::PRECONDITION_FAILED) {
+            bucket.throttle();
+        }
+        Ok(res)
+    }
+
+    // 按返回的 code 调整限速
+    fn observe(&self, url: &str, code: i64) {
+        if let Some(bucket) = self.bucket(url) {
+            bucket.observe(code);
+        }
     }
 
     // 获取内部的Client
-    pub fn client(&self) -> &Client {
-        &self.client
+    pub fn client(&self) -> Client {
+        self.credentials.read().unwrap().client.clone()
     }
 
     // 获取CSRF令牌
-    pub fn csrf(&self) -> &str {
-        &self.csrf
+    pub fn csrf(&self) -> String {
+        self.credentials.read().unwrap().csrf.clone()
+    }
+
+    // 获取当前的登录凭据，Cookie 刷新后会变化
+    pub fn session(&self) -> Session {
+        self.credentials.read().unwrap().session.clone()
     }
 
-    // 发送GET请求并返回JSON响应
+    // 发送GET请求并返回JSON响应，code 不为 0 时返回错误；未登录时刷新 Cookie 后重试一次
     pub async fn get_json<T: IntoUrl>(&self, url: T) -> Result<Value> {
+        let url = self.url(url)?;
+        let csrf = self.csrf();
+        match self.get_json_once(&url).await {
+            Err(e)
+                if e.api_error() == Some(&BiliApiError::NotLoggedIn)
+                    && self.refresh_after(&csrf).await =>
+            {
+                self.get_json_once(&self.renew_csrf(&url, &csrf)).await
+            }
+            res => res,
+        }
+    }
+
+    async fn get_json_once(&self, url: &str) -> Result<Value> {
         let res: Value = self
-            .client
-            .get(url)
-            .send()
+            .execute(url, self.client().get(url))
             .await?
             .error_for_status()?
             .json()
             .await?;
         debug!("Got res: {}", res);
+        let envelope = Envelope::<IgnoredAny>::deserialize(&res)?;
+        self.observe(url, envelope.code);
+        envelope.into_result().map_err(Error::Api)?;
         Ok(res)
     }
 
+    // 发送GET请求，检查 code 后将 data 反序列化为指定类型
+    pub async fn fetch_api<T: DeserializeOwned>(&self, url: impl IntoUrl) -> Result<T> {
+        let url = self.url(url)?;
+        let csrf = self.csrf();
+        let mut res: Envelope = self.fetch_data(url.as_str()).await?;
+        self.observe(&url, res.code);
+        if res.code == NOT_LOGGED_IN && self.refresh_after(&csrf).await {
+            res = self.fetch_data(self.renew_csrf(&url, &csrf)).await?;
+            self.observe(&url, res.code);
+        }
+        let data = res.into_result().map_err(Error::Api)?;
+        Ok(serde_json::from_value(data.unwrap_or_default())?)
+    }
+
     // 发送GET请求并反序列化为指定类型
     pub async fn fetch_data<T: DeserializeOwned>(&self, url: impl IntoUrl) -> Result<T> {
-        let res = self.client.get(url).send().await?.error_for_status()?;
+        let url = self.url(url)?;
+        let res = self
+            .execute(&url, self.client().get(&url))
+            .await?
+            .error_for_status()?;
         debug!("{:?}", &res);
         Ok(res.json().await?)
     }
 
     // 发送POST请求（JSON格式）
     pub async fn post_json<T: IntoUrl>(&self, url: T, json_data: &Value) -> Result<Response> {
-        Ok(self.client.post(url).json(json_data).send().await?)
+        let url = self.url(url)?;
+        self.execute(&url, self.client().post(&url).json(json_data))
+            .await
     }
 
     // 发送POST请求（表单格式）
@@ -96,19 +349,177 @@ impl ApiService {
         url: T,
         form_data: &[(&str, String)],
     ) -> Result<Response> {
-        Ok(self.client.post(url).form(form_data).send().await?)
+        let url = self.url(url)?;
+        self.execute(&url, self.client().post(&url).form(form_data))
+            .await
+    }
+
+    // 发送由 RemoveAble::plan 生成的请求，返回未检查 code 的响应；未登录时刷新 Cookie 后换用新的 csrf 重试一次
+    pub async fn send(&self, request: &PlannedRequest) -> Result<Envelope> {
+        let csrf = self.csrf();
+        let res = self.send_once(request).await?;
+        if res.code == NOT_LOGGED_IN && self.refresh_after(&csrf).await {
+            return self
+                .send_once(&request.with_csrf(&csrf, &self.csrf()))
+                .await;
+        }
+        Ok(res)
+    }
+
+    async fn send_once(&self, request: &PlannedRequest) -> Result<Envelope> {
+        if let Some(bucket) = self.limiter.delete() {
+            bucket.acquire().await;
+        }
+        let (url, res) = match request {
+            PlannedRequest::Form { url, form } => (url, self.post_form(url, form).await?),
+            PlannedRequest::Json { url, body } => (url, self.post_json(url, body).await?),
+        };
+        let res: Envelope = res.error_for_status()?.json().await?;
+        debug!("Got res: {:?}", res);
+        self.observe(url, res.code);
+        Ok(res)
+    }
+
+    // 替换地址参数中旧的 csrf
+    fn renew_csrf(&self, url: &str, old: &str) -> String {
+        url.replace(&format!("csrf={old}"), &format!("csrf={}", self.csrf()))
+    }
+
+    /// 用 `csrf` 发出的请求返回未登录后调用，Cookie 已被其他请求刷新或刷新成功时返回 true
+    async fn refresh_after(&self, csrf: &str) -> bool {
+        let _guard = self.refresh_lock.lock().await;
+        if self.csrf() != csrf {
+            return true;
+        }
+        if self.session().refresh_token.is_none() {
+            return false;
+        }
+        match self.refresh_cookie().await {
+            Ok(()) => {
+                info!("Cookie refreshed");
+                true
+            }
+            Err(e) => {
+                warn!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// 按网页端的流程用 refresh_token 换取新的 Cookie，之后的请求都使用新的 Cookie
+    pub async fn refresh_cookie(&self) -> Result<()> {
+        let old = self.session();
+        let refresh_token = old
+            .refresh_token
+            .clone()
+            .ok_or_else(|| refresh_error("no refresh_token"))?;
+        let csrf = self.csrf();
+
+        let path = correspond_path(chrono::Utc::now().timestamp_millis())?;
+        let url = self.url(format!("{WWW}/correspond/1/{path}"))?;
+        let html = self
+            .execute(&url, self.client().get(&url))
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let refresh_csrf = html
+            .split_once(r#"<div id="1-name">"#)
+            .and_then(|(_, rest)| rest.split_once("</div>"))
+            .map(|(v, _)| v.trim().to_string())
+            .ok_or_else(|| refresh_error("refresh_csrf not found"))?;
+
+        let res = self
+            .post_form(
+                format!("{PASSPORT}/x/passport-login/web/cookie/refresh"),
+                &[
+                    ("csrf", csrf),
+                    ("refresh_csrf", refresh_csrf),
+                    ("source", "main_web".to_string()),
+                    ("refresh_token", refresh_token.clone()),
+                ],
+            )
+            .await?
+            .error_for_status()?;
+        let cookie = merge_cookie(&old.cookie, res.headers());
+        let data = res
+            .json::<Envelope<RefreshData>>()
+            .await?
+            .into_result()
+            .map_err(Error::Api)?
+            .ok_or_else(|| refresh_error("empty response"))?;
+        let session = Session {
+            cookie,
+            refresh_token: Some(data.refresh_token),
+        };
+        *self.credentials.write().unwrap() = Credentials::new(session.clone())?;
+        if let Some(store) = &self.account_store {
+            store.update_session(&session);
+        }
+
+        // 让旧的 refresh_token 失效，失败不影响新的 Cookie
+        let confirm = self
+            .post_form(
+                format!("{PASSPORT}/x/passport-login/web/confirm/refresh"),
+                &[("csrf", self.csrf()), ("refresh_token", refresh_token)],
+            )
+            .await;
+        if let Err(e) = confirm {
+            warn!("Failed to confirm cookie refresh: {}", e);
+        }
+        Ok(())
     }
 
     // 获取用户ID
     pub async fn get_uid(&self) -> Result<u64> {
-        let json_res = self
-            .get_json("https://api.bilibili.com/x/member/web/account")
+        Ok(self.get_account().await?.0)
+    }
+
+    // 获取用户ID和昵称
+    pub async fn get_account(&self) -> Result<(u64, String)> {
+        let data = self
+            .fetch_api::<member_account::Data>("https://api.bilibili.com/x/member/web/account")
             .await
             .map_err(|e| match e {
                 Error::RequestFailed(inner) => Error::GetUIDError(inner),
                 other => other,
             })?;
-        let uid = json_res["data"]["mid"].as_u64().unwrap();
-        Ok(uid)
+        Ok((data.mid, data.uname))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct RefreshData {
+    refresh_token: String,
+}
+
+fn refresh_error(msg: impl ToString) -> Error {
+    Error::RefreshCookieError(Arc::new(msg.to_string()))
+}
+
+/// 用 bilibili 公钥以 RSA-OAEP 加密 `refresh_{毫秒时间戳}`
+fn correspond_path(timestamp: i64) -> Result<String> {
+    let key = RsaPublicKey::from_public_key_pem(REFRESH_PUBLIC_KEY).map_err(refresh_error)?;
+    let sealed = key
+        .encrypt(
+            &mut OsRng,
+            Oaep::new::<Sha256>(),
+            format!("refresh_{timestamp}").as_bytes(),
+        )
+        .map_err(refresh_error)?;
+    Ok(sealed.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// 用响应的 Set-Cookie 更新 Cookie 字符串
+fn merge_cookie(cookie: &str, headers: &header::HeaderMap) -> String {
+    let mut jar = CookieJar::from_header(cookie);
+    let set_cookies = headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|v| v.split(';').next()?.split_once('='));
+    for (name, value) in set_cookies {
+        jar.set(name.trim(), value.trim());
     }
+    jar.to_string()
 }
@@ -1,13 +1,17 @@
 pub mod aicu;
+pub mod dynamic;
 pub mod official;
 
 use super::api_service::ApiService;
-use crate::http::notify::Notify;
-use crate::types::{Error, RemoveAble, Result};
-use serde_json::Value;
+use crate::filter::Filterable;
+use crate::http::notify::{Feed, Notify, NotifyKey};
+use crate::screens::main;
+use crate::types::{Error, Item, Origin, Parent, PlannedRequest, RemoveAble, Result};
+use crate::{Type, ViewerMsg};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Comment {
     pub oid: u64,
     pub r#type: u8,
@@ -16,9 +20,18 @@ pub struct Comment {
     pub notify_id: Option<u64>,
     /// 删除通知用 0为收到赞的 1为收到评论的 2为被At的
     pub tp: Option<u8>,
+    /// 评论或通知的时间戳（秒）
+    #[serde(default)]
+    pub time: Option<u64>,
+    /// 从自己动态下的评论区找到的
+    #[serde(default)]
+    pub from_dynamic: bool,
+    /// 所在的视频、动态等的链接，来自通知
+    #[serde(default)]
+    pub uri: Option<String>,
 }
 impl Comment {
-    fn new(oid: u64, r#type: u8, content: String) -> Comment {
+    fn new(oid: u64, r#type: u8, content: String, time: Option<u64>) -> Comment {
         Comment {
             oid,
             r#type,
@@ -26,6 +39,9 @@ impl Comment {
             is_selected: true,
             notify_id: None,
             tp: None,
+            time,
+            from_dynamic: false,
+            uri: None,
         }
     }
     pub fn new_with_notify(
@@ -34,6 +50,7 @@ impl Comment {
         content: String,
         notify_id: u64,
         tp: u8,
+        time: u64,
     ) -> Comment {
         Comment {
             oid,
@@ -42,49 +59,137 @@ impl Comment {
             is_selected: true,
             notify_id: Some(notify_id),
             tp: Some(tp),
+            time: Some(time),
+            from_dynamic: false,
+            uri: None,
         }
     }
 }
-impl RemoveAble for Comment {
-    async fn remove(&self, rpid: u64, api: Arc<ApiService>) -> Result<u64> {
-        let json_res: Value = if self.r#type == 11 {
-            let form_data = [
-                ("oid", self.oid.to_string()),
-                ("type", self.r#type.to_string()),
-                ("rpid", rpid.to_string()),
-            ];
-            api.post_form(
-                format!(
-                    "https://api.bilibili.com/x/v2/reply/del?csrf={}",
-                    api.csrf()
-                ),
-                &form_data,
-            )
-            .await?
-            .json()
-            .await?
-        } else {
-            let form_data = [
-                ("oid", self.oid.to_string()),
-                ("type", self.r#type.to_string()),
-                ("rpid", rpid.to_string()),
-                ("csrf", api.csrf().to_string()),
-            ];
-            api.post_form("https://api.bilibili.com/x/v2/reply/del", &form_data)
-                .await?
-                .json()
-                .await?
-        };
-        if json_res["code"].as_i64().unwrap() == 0 {
-            // 如果is_some则删除通知
-            if let Some(notify_id) = self.notify_id {
-                Notify::new(String::new(), self.tp.unwrap())
-                    .remove(notify_id, api.clone())
-                    .await?;
+impl Filterable for Comment {
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn time(&self) -> Option<u64> {
+        self.time
+    }
+}
+impl Comment {
+    fn delete_request(&self, rpid: u64, csrf: &str) -> PlannedRequest {
+        if self.r#type == 11 {
+            PlannedRequest::Form {
+                url: format!("https://api.bilibili.com/x/v2/reply/del?csrf={}", csrf),
+                form: vec![
+                    ("oid", self.oid.to_string()),
+                    ("type", self.r#type.to_string()),
+                    ("rpid", rpid.to_string()),
+                ],
             }
-            Ok(rpid)
         } else {
-            Err(Error::DeleteCommentError(json_res.into()))
+            PlannedRequest::Form {
+                url: "https://api.bilibili.com/x/v2/reply/del".to_string(),
+                form: vec![
+                    ("oid", self.oid.to_string()),
+                    ("type", self.r#type.to_string()),
+                    ("rpid", rpid.to_string()),
+                    ("csrf", csrf.to_string()),
+                ],
+            }
         }
     }
+
+    /// 随评论一起删除的通知
+    pub fn notify_key(&self) -> Option<NotifyKey> {
+        Some(NotifyKey::new(Feed::from_tp(self.tp?), self.notify_id?))
+    }
+
+    fn notify(&self) -> Option<(NotifyKey, Notify)> {
+        Some((
+            self.notify_key()?,
+            Notify::new(String::new(), self.tp?, None),
+        ))
+    }
+}
+impl RemoveAble for Comment {
+    type Key = u64;
+
+    fn steps(&self) -> usize {
+        1 + usize::from(self.notify().is_some())
+    }
+
+    async fn remove_step(&self, rpid: u64, step: usize, api: Arc<ApiService>) -> Result<()> {
+        match (step, self.notify()) {
+            (0, _) => {
+                api.send(&self.delete_request(rpid, &api.csrf()))
+                    .await?
+                    .into_result()
+                    .map_err(Error::DeleteCommentError)?;
+            }
+            // 第二步删除通知
+            (_, Some((key, notify))) => notify.remove_step(key, 0, api).await?,
+            (_, None) => {}
+        }
+        Ok(())
+    }
+
+    fn plan(&self, rpid: u64, csrf: &str) -> Vec<PlannedRequest> {
+        let mut requests = vec![self.delete_request(rpid, csrf)];
+        if let Some((key, notify)) = self.notify() {
+            requests.extend(notify.plan(key, csrf));
+        }
+        requests
+    }
+}
+
+impl Item for Comment {
+    const TYPE: Type = Type::Comment;
+
+    fn is_selected(&self) -> bool {
+        self.is_selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.is_selected = selected;
+    }
+
+    /// 随之删除的通知也从通知列表中移除
+    fn cascade(&self) -> Option<main::Message> {
+        self.notify_key()
+            .map(|key| ViewerMsg::<Notify>::Deleted { key }.into())
+    }
+
+    fn origin(&self) -> Origin {
+        match self.tp {
+            None if self.from_dynamic => Origin::Dynamic,
+            Some(0) => Origin::Liked,
+            Some(1) => Origin::Replyed,
+            Some(_) => Origin::Ated,
+            None => Origin::Aicu,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.r#type {
+            1 => "video",
+            11 | 17 => "dynamic",
+            12 => "article",
+            _ => "other",
+        }
+    }
+
+    fn link(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    fn parent(&self) -> Option<Parent> {
+        Some(match self.r#type {
+            1 => Parent::Video(self.oid),
+            11 | 17 => Parent::Dynamic(self.oid),
+            12 => Parent::Article(self.oid),
+            r#type => Parent::Other {
+                r#type,
+                oid: self.oid,
+            },
+        })
+    }
 }
@@ -0,0 +1,202 @@
+//! 遍历自己发布的动态（包括投稿视频、专栏等产生的动态），在其评论区中找出自己发的评论
+
+use crate::http::api_service::ApiService;
+use crate::http::comment::Comment;
+use crate::http::response::official::{reply_list, space_dynamic};
+use crate::http::source::{self, decode_cursor, Items, Page, Source};
+use crate::types::Result;
+use iced::futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+/// 评论区每页的条数
+const PAGE_SIZE: u32 = 20;
+
+#[derive(Serialize, Deserialize)]
+struct DynamicCursor {
+    uid: u64,
+    /// 当前这页动态的评论区 (oid, type)
+    areas: Vec<(u64, u8)>,
+    /// 正在获取第几个评论区，以及该评论区的页码
+    index: usize,
+    page: u32,
+    /// 下一页动态的 offset，已是最后一页时为 None
+    offset: Option<String>,
+    /// 待翻页的楼中楼 (oid, type, root)，以及第一个的页码
+    #[serde(default)]
+    roots: Vec<(u64, u8, u64)>,
+    #[serde(default = "first_page")]
+    sub_page: u32,
+}
+
+fn first_page() -> u32 {
+    1
+}
+
+/// 自己动态下的评论区
+pub struct DynamicComments;
+
+impl Source for DynamicComments {
+    fn id(&self) -> &'static str {
+        "dynamic_comment"
+    }
+
+    fn name(&self) -> &'static str {
+        "Dynamic comments"
+    }
+
+    /// 每次获取一页动态、一页评论或一页楼中楼
+    fn fetch_page<'a>(
+        &'a self,
+        api: &'a Arc<ApiService>,
+        cursor: Option<source::Cursor>,
+    ) -> BoxFuture<'a, Result<Page>> {
+        Box::pin(async move {
+            let mut cursor = match decode_cursor::<DynamicCursor>(cursor)? {
+                Some(cursor) => cursor,
+                None => DynamicCursor {
+                    uid: api.get_uid().await?,
+                    areas: Vec::new(),
+                    index: 0,
+                    page: 1,
+                    offset: Some(String::new()),
+                    roots: Vec::new(),
+                    sub_page: 1,
+                },
+            };
+
+            let mut items = Items::default();
+            if let Some(&(oid, r#type, root)) = cursor.roots.first() {
+                let more = fetch_sub_replies(
+                    api,
+                    &mut items.comments,
+                    cursor.uid,
+                    (oid, r#type),
+                    root,
+                    cursor.sub_page,
+                )
+                .await?;
+                if more {
+                    cursor.sub_page += 1;
+                } else {
+                    cursor.roots.remove(0);
+                    cursor.sub_page = 1;
+                }
+                return Page::next(items, &cursor);
+            }
+            match cursor.areas.get(cursor.index).copied() {
+                Some(area) => {
+                    let (more, roots) =
+                        fetch_reply_page(api, &mut items.comments, cursor.uid, area, cursor.page)
+                            .await?;
+                    cursor
+                        .roots
+                        .extend(roots.into_iter().map(|root| (area.0, area.1, root)));
+                    if more {
+                        cursor.page += 1;
+                    } else {
+                        cursor.index += 1;
+                        cursor.page = 1;
+                    }
+                }
+                None => {
+                    let Some(offset) = cursor.offset.take() else {
+                        return Ok(Page::end(items));
+                    };
+                    let feed = api
+                        .fetch_api::<space_dynamic::Data>(format!(
+                            "https://api.bilibili.com/x/polymer/web-dynamic/v1/feed/space?host_mid={}&offset={}",
+                            cursor.uid, offset
+                        ))
+                        .await?;
+                    // 没有评论区的动态直接跳过
+                    cursor.areas = feed
+                        .items
+                        .iter()
+                        .filter_map(space_dynamic::Item::comment_area)
+                        .collect();
+                    cursor.index = 0;
+                    cursor.page = 1;
+                    cursor.offset =
+                        (feed.has_more && !feed.offset.is_empty()).then_some(feed.offset);
+                }
+            }
+
+            if cursor.index >= cursor.areas.len()
+                && cursor.offset.is_none()
+                && cursor.roots.is_empty()
+            {
+                info!("自己动态下的评论处理完毕");
+                return Ok(Page::end(items));
+            }
+            Page::next(items, &cursor)
+        })
+    }
+}
+
+/// 读取一页一级评论，收集其中自己的评论（包括附带的楼中楼）
+///
+/// 返回是否还有下一页，以及楼中楼需要另外翻页的一级评论
+async fn fetch_reply_page(
+    api: &ApiService,
+    data: &mut HashMap<u64, Comment>,
+    uid: u64,
+    (oid, r#type): (u64, u8),
+    page: u32,
+) -> Result<(bool, Vec<u64>)> {
+    let res = api
+        .fetch_api::<reply_list::Data>(format!(
+            "https://api.bilibili.com/x/v2/reply?type={}&oid={}&pn={}&ps={}&sort=0",
+            r#type, oid, page, PAGE_SIZE
+        ))
+        .await?;
+    let replies = res.replies.unwrap_or_default();
+    let more = !replies.is_empty() && res.page.has_next();
+    let mut roots = Vec::new();
+    for reply in replies {
+        // 一级评论只附带少量楼中楼，其余的需要另外翻页
+        let inline = reply.replies.as_ref().map_or(0, Vec::len) as u64;
+        if reply.rcount > inline {
+            roots.push(reply.rpid);
+        }
+        collect(data, uid, (oid, r#type), reply);
+    }
+    Ok((more, roots))
+}
+
+/// 读取一页楼中楼，返回是否还有下一页
+async fn fetch_sub_replies(
+    api: &ApiService,
+    data: &mut HashMap<u64, Comment>,
+    uid: u64,
+    (oid, r#type): (u64, u8),
+    root: u64,
+    page: u32,
+) -> Result<bool> {
+    let res = api
+        .fetch_api::<reply_list::Data>(format!(
+            "https://api.bilibili.com/x/v2/reply/reply?type={}&oid={}&root={}&pn={}&ps={}",
+            r#type, oid, root, page, PAGE_SIZE
+        ))
+        .await?;
+    let replies = res.replies.unwrap_or_default();
+    let more = !replies.is_empty() && res.page.has_next();
+    for reply in replies {
+        collect(data, uid, (oid, r#type), reply);
+    }
+    Ok(more)
+}
+
+fn collect(data: &mut HashMap<u64, Comment>, uid: u64, area: (u64, u8), reply: reply_list::Reply) {
+    if reply.mid == uid {
+        data.entry(reply.rpid).or_insert_with(|| Comment {
+            from_dynamic: true,
+            ..Comment::new(area.0, area.1, reply.content.message, Some(reply.ctime))
+        });
+    }
+    for sub in reply.replies.into_iter().flatten() {
+        collect(data, uid, area, sub);
+    }
+}
@@ -0,0 +1,231 @@
+//! 解析各种格式的 Cookie：请求头、浏览器的 “Copy as cURL”、Netscape cookies.txt 与 EditThisCookie 导出的 JSON
+
+use crate::types::CookieError;
+use serde::Deserialize;
+use std::fmt;
+
+/// 登录必须的 Cookie
+pub const REQUIRED: [&str; 3] = ["SESSDATA", "bili_jct", "DedeUserID"];
+
+/// 按出现顺序保存的 Cookie，同名的以后出现的为准
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieJar(Vec<(String, String)>);
+
+impl CookieJar {
+    /// 自动识别格式，并检查登录必须的字段
+    pub fn parse(input: &str) -> Result<Self, CookieError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(CookieError::Empty);
+        }
+        let jar = if input.starts_with('[') || input.starts_with('{') {
+            Self::from_json(input)?
+        } else if input.starts_with("curl ") {
+            Self::from_curl(input)?
+        } else if input.lines().any(|line| line.split('\t').count() == 7) {
+            Self::from_netscape(input)
+        } else {
+            Self::from_header(input)
+        };
+        jar.validate()?;
+        Ok(jar)
+    }
+
+    /// `Cookie` 请求头的值，可以带 `Cookie:` 前缀
+    pub fn from_header(header: &str) -> Self {
+        let header = header.trim();
+        let header = strip_prefix_ignore_case(header, "cookie:").unwrap_or(header);
+        let mut jar = Self::default();
+        for (name, value) in header.split(';').filter_map(|pair| pair.split_once('=')) {
+            jar.set(name.trim(), value.trim());
+        }
+        jar
+    }
+
+    /// 浏览器开发者工具中 “Copy as cURL (bash)” 得到的命令
+    fn from_curl(command: &str) -> Result<Self, CookieError> {
+        let args = shell_words(command);
+        let mut jar = Self::default();
+        let mut found = false;
+        for pair in args.windows(2) {
+            let value = match pair[0].as_str() {
+                "-H" | "--header" => match strip_prefix_ignore_case(pair[1].trim(), "cookie:") {
+                    Some(v) => v,
+                    None => continue,
+                },
+                "-b" | "--cookie" => &pair[1],
+                _ => continue,
+            };
+            found = true;
+            for (name, value) in Self::from_header(value).0 {
+                jar.set(&name, &value);
+            }
+        }
+        if found {
+            Ok(jar)
+        } else {
+            Err(CookieError::NoCookieInCurl)
+        }
+    }
+
+    /// curl / wget 使用的 cookies.txt，每行 7 个以 Tab 分隔的字段
+    fn from_netscape(text: &str) -> Self {
+        let mut jar = Self::default();
+        for line in text.lines() {
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<_> = line.split('\t').collect();
+            if let [domain, _, _, _, _, name, value] = fields[..] {
+                if is_bilibili_host(domain) {
+                    jar.set(name, value.trim());
+                }
+            }
+        }
+        jar
+    }
+
+    /// EditThisCookie 等扩展导出的 `[{"domain", "name", "value", ...}]`
+    fn from_json(text: &str) -> Result<Self, CookieError> {
+        #[derive(Deserialize)]
+        struct Entry {
+            name: String,
+            value: String,
+            #[serde(default)]
+            domain: String,
+        }
+
+        let entries: Vec<Entry> = serde_json::from_str(text)
+            .or_else(|_| serde_json::from_str(text).map(|e: Entry| vec![e]))
+            .map_err(|e| CookieError::InvalidJson(e.to_string()))?;
+        let mut jar = Self::default();
+        for entry in entries.iter().filter(|e| is_bilibili_host(&e.domain)) {
+            jar.set(&entry.name, &entry.value);
+        }
+        Ok(jar)
+    }
+
+    fn validate(&self) -> Result<(), CookieError> {
+        let missing: Vec<_> = REQUIRED
+            .into_iter()
+            .filter(|name| !self.get(name).is_some_and(|v| !v.is_empty()))
+            .collect();
+        if !missing.is_empty() {
+            return Err(CookieError::Missing(missing.join(", ")));
+        }
+        if self.uid().is_none() {
+            let uid = self.get("DedeUserID").unwrap_or_default();
+            return Err(CookieError::InvalidUid(uid.to_string()));
+        }
+        if let Some((name, _)) = self.0.iter().find(|(k, v)| !k.is_ascii() || !v.is_ascii()) {
+            return Err(CookieError::NonAscii(name.clone()));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.0.iter_mut().find(|(k, _)| k == name) {
+            Some(pair) => pair.1 = value.to_string(),
+            None => self.0.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// bili_jct，即请求时的 csrf
+    pub fn csrf(&self) -> &str {
+        self.get("bili_jct").unwrap_or_default()
+    }
+
+    /// DedeUserID
+    pub fn uid(&self) -> Option<u64> {
+        self.get("DedeUserID")?.parse().ok()
+    }
+}
+
+/// 格式化为 `Cookie` 请求头的值
+impl fmt::Display for CookieJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.get(..prefix.len())
+        .filter(|p| p.eq_ignore_ascii_case(prefix))
+        .map(|_| s[prefix.len()..].trim_start())
+}
+
+/// `bilibili.com` 或其子域名，Cookie 的域名可以带前导的 `.`
+///
+/// 导入 Cookie、读取浏览器数据库与打开链接共用，不接受 `notbilibili.com` 等相似的域名
+pub fn is_bilibili_host(host: &str) -> bool {
+    let host = host.strip_prefix('.').unwrap_or(host).to_ascii_lowercase();
+    host == "bilibili.com" || host.ends_with(".bilibili.com")
+}
+
+/// 按 shell 的规则切分参数，只处理引号、反斜杠转义与续行
+fn shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // bash 的 $'...'
+            '$' if chars.peek() == Some(&'\'') => in_word = true,
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n' | '\r') | None => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
@@ -1,62 +1,149 @@
 use super::api_service::ApiService;
-use crate::http::notify::Notify;
-use crate::types::{Error, RemoveAble, Result};
-use serde_json::Value;
+use crate::filter::Filterable;
+use crate::http::notify::{Feed, Notify, NotifyKey};
+use crate::screens::main;
+use crate::types::{Error, Item, Origin, Parent, PlannedRequest, RemoveAble, Result};
+use crate::{Type, ViewerMsg};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 pub mod aicu;
 pub mod official;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Danmu {
     pub content: String,
-    cid: u64,
+    pub cid: u64,
     // r#type: u8,
     pub is_selected: bool,
     pub notify_id: Option<u64>,
+    /// 发送或被点赞的时间戳（秒）
+    #[serde(default)]
+    pub time: Option<u64>,
+    /// 所在视频的链接，来自通知
+    #[serde(default)]
+    pub uri: Option<String>,
 }
 impl Danmu {
-    fn new(content: String, cid: u64) -> Danmu {
+    fn new(content: String, cid: u64, time: Option<u64>) -> Danmu {
         Danmu {
             content,
             cid,
             is_selected: true,
             notify_id: None,
+            time,
+            uri: None,
         }
     }
-    pub fn new_with_notify(content: String, cid: u64, notify_id: u64) -> Danmu {
+    pub fn new_with_notify(content: String, cid: u64, notify_id: u64, time: u64) -> Danmu {
         Danmu {
             content,
             cid,
             is_selected: true,
             notify_id: Some(notify_id),
+            time: Some(time),
+            uri: None,
         }
     }
 }
 
+impl Filterable for Danmu {
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn time(&self) -> Option<u64> {
+        self.time
+    }
+}
+
+impl Danmu {
+    fn delete_request(&self, dmid: u64, csrf: &str) -> PlannedRequest {
+        PlannedRequest::Form {
+            url: "https://api.bilibili.com/x/msgfeed/del".to_string(),
+            form: vec![
+                ("dmid", dmid.to_string()),
+                ("cid", self.cid.to_string()),
+                ("type", 1.to_string()),
+                ("csrf", csrf.to_string()),
+            ],
+        }
+    }
+
+    /// 随弹幕一起删除的通知
+    pub fn notify_key(&self) -> Option<NotifyKey> {
+        Some(NotifyKey::new(Feed::Liked, self.notify_id?))
+    }
+
+    fn notify(&self) -> Option<(NotifyKey, Notify)> {
+        Some((self.notify_key()?, Notify::new(String::new(), 0, None)))
+    }
+}
+
 impl RemoveAble for Danmu {
-    async fn remove(&self, dmid: u64, api: Arc<ApiService>) -> Result<u64> {
-        let form_data = [
-            ("dmid", dmid.to_string()),
-            ("cid", self.cid.to_string()),
-            ("type", 1.to_string()),
-            ("csrf", api.csrf().to_string()),
-        ];
-        let json_res: Value = api
-            .post_form("https://api.bilibili.com/x/msgfeed/del", &form_data)
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        if json_res["code"].as_i64().unwrap() == 0 {
-            if let Some(notify_id) = self.notify_id {
-                Notify::new(String::new(), 0)
-                    .remove(notify_id, api.clone())
-                    .await?;
+    type Key = u64;
+
+    fn steps(&self) -> usize {
+        1 + usize::from(self.notify().is_some())
+    }
+
+    async fn remove_step(&self, dmid: u64, step: usize, api: Arc<ApiService>) -> Result<()> {
+        match (step, self.notify()) {
+            (0, _) => {
+                api.send(&self.delete_request(dmid, &api.csrf()))
+                    .await?
+                    .into_result()
+                    .map_err(Error::DeleteDanmuError)?;
             }
-            Ok(dmid)
+            (_, Some((key, notify))) => notify.remove_step(key, 0, api).await?,
+            (_, None) => {}
+        }
+        Ok(())
+    }
+
+    fn plan(&self, dmid: u64, csrf: &str) -> Vec<PlannedRequest> {
+        let mut requests = vec![self.delete_request(dmid, csrf)];
+        if let Some((key, notify)) = self.notify() {
+            requests.extend(notify.plan(key, csrf));
+        }
+        requests
+    }
+}
+
+impl Item for Danmu {
+    const TYPE: Type = Type::Danmu;
+
+    fn is_selected(&self) -> bool {
+        self.is_selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.is_selected = selected;
+    }
+
+    /// 随之删除的通知也从通知列表中移除
+    fn cascade(&self) -> Option<main::Message> {
+        self.notify_key()
+            .map(|key| ViewerMsg::<Notify>::Deleted { key }.into())
+    }
+
+    fn origin(&self) -> Origin {
+        if self.notify_id.is_some() {
+            Origin::Liked
         } else {
-            Err(Error::DeleteDanmuError(json_res.into()))
+            Origin::Aicu
         }
     }
+
+    fn kind(&self) -> &'static str {
+        "danmu"
+    }
+
+    fn link(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    fn parent(&self) -> Option<Parent> {
+        Some(Parent::Cid(self.cid))
+    }
 }
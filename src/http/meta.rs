@@ -0,0 +1,132 @@
+//! 评论、弹幕所在的视频、动态、专栏的标题与链接
+
+use super::api_service::ApiService;
+use super::cookie::is_bilibili_host;
+use super::response::official::{article_view, dynamic_detail, video_view};
+use crate::types::{Error, Parent, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::{Arc, LazyLock};
+
+static BVID_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"BV[0-9A-Za-z]{10}").unwrap());
+static DYNAMIC_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"t\.bilibili\.com/(\d+)").unwrap());
+
+/// 动态没有标题时用正文代替，只保留开头
+const TITLE_CHARS: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentInfo {
+    pub title: String,
+    pub url: String,
+}
+
+/// 不需要请求就能得到的链接
+pub fn default_url(parent: Parent) -> Option<String> {
+    match parent {
+        Parent::Video(aid) => Some(format!("https://www.bilibili.com/video/av{}", aid)),
+        Parent::Dynamic(id) => Some(format!("https://t.bilibili.com/{}", id)),
+        Parent::Article(cv) => Some(format!("https://www.bilibili.com/read/cv{}", cv)),
+        Parent::Cid(_) | Parent::Other { .. } => None,
+    }
+}
+
+/// 查询标题；`uri` 为通知中的链接，弹幕只能通过其中的 bv 号查询
+///
+/// 无法查询的类型返回 None
+pub async fn resolve(
+    api: &ApiService,
+    parent: Parent,
+    uri: Option<&str>,
+) -> Result<Option<ParentInfo>> {
+    let info = match parent {
+        Parent::Video(aid) => {
+            let url = format!("https://api.bilibili.com/x/web-interface/view?aid={}", aid);
+            video_info(api, url, default_url(parent)).await?
+        }
+        Parent::Cid(_) => {
+            let Some(bvid) = uri.and_then(|u| BVID_REGEX.find(u)) else {
+                return Ok(None);
+            };
+            let url = format!(
+                "https://api.bilibili.com/x/web-interface/view?bvid={}",
+                bvid.as_str()
+            );
+            video_info(api, url, uri.map(str::to_string)).await?
+        }
+        Parent::Dynamic(oid) => {
+            // 带图动态的 oid 不是动态 id，优先使用链接中的 id
+            let id = uri
+                .and_then(|u| DYNAMIC_REGEX.captures(u))
+                .and_then(|c| c[1].parse().ok())
+                .unwrap_or(oid);
+            let url = format!(
+                "https://api.bilibili.com/x/polymer/web-dynamic/v1/detail?id={}",
+                id
+            );
+            let data = api.fetch_api::<dynamic_detail::Data>(url).await?;
+            ParentInfo {
+                title: data
+                    .title()
+                    .map(|t| shorten(&t))
+                    .unwrap_or_else(|| parent.to_string()),
+                url: default_url(Parent::Dynamic(id)).unwrap(),
+            }
+        }
+        Parent::Article(cv) => {
+            let url = format!("https://api.bilibili.com/x/article/viewinfo?id={}", cv);
+            let data = api.fetch_api::<article_view::Data>(url).await?;
+            ParentInfo {
+                title: data.title,
+                url: default_url(parent).unwrap(),
+            }
+        }
+        Parent::Other { .. } => return Ok(None),
+    };
+    Ok(Some(info))
+}
+
+async fn video_info(api: &ApiService, url: String, fallback: Option<String>) -> Result<ParentInfo> {
+    let data = api.fetch_api::<video_view::Data>(url).await?;
+    Ok(ParentInfo {
+        title: data.title,
+        url: data.redirect_url.or(fallback).unwrap_or_default(),
+    })
+}
+
+fn shorten(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > TITLE_CHARS {
+        format!("{}…", line.chars().take(TITLE_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// 只允许 bilibili 的 https 链接，避免把获取到的任意链接交给系统打开
+pub fn is_allowed_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default();
+    url.scheme() == "https" && (is_bilibili_host(host) || host == "b23.tv")
+}
+
+/// 用系统默认的浏览器打开链接，见 [`is_allowed_url`]
+pub fn open_in_browser(url: &str) -> Result<()> {
+    if !is_allowed_url(url) {
+        return Err(Error::UnrecognizedURI(Arc::new(url.to_string())));
+    }
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("rundll32");
+        cmd.arg("url.dll,FileProtocolHandler");
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    cmd.arg(url).spawn()?;
+    Ok(())
+}
@@ -1,7 +1,11 @@
 pub mod api_service;
 pub mod comment;
+pub mod cookie;
 pub mod danmu;
+pub mod meta;
 pub mod notify;
 pub mod qr_code;
+pub mod rate_limit;
 pub mod response;
+pub mod source;
 pub mod utility;
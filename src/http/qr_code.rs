@@ -1,5 +1,7 @@
 use crate::http::api_service::ApiService;
-use crate::types::Result;
+use crate::http::cookie::CookieJar;
+use crate::http::response::official::{qrcode_generate, qrcode_poll};
+use crate::types::{Result, Session};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -8,30 +10,47 @@ pub struct QRdata {
     pub key: String,
 }
 impl QRdata {
-    pub async fn request_qrcode() -> Result<QRdata> {
-        let api = Arc::new(ApiService::default());
-        let a = api
-            .get_json("https://passport.bilibili.com/x/passport-login/web/qrcode/generate")
+    pub async fn request_qrcode(api: Arc<ApiService>) -> Result<QRdata> {
+        let data = api
+            .fetch_api::<qrcode_generate::Data>(
+                "https://passport.bilibili.com/x/passport-login/web/qrcode/generate",
+            )
             .await?;
         Ok(QRdata {
-            url: a["data"]["url"].as_str().unwrap().to_string(),
-            key: a["data"]["qrcode_key"].as_str().unwrap().to_string(),
+            url: data.url,
+            key: data.qrcode_key,
         })
     }
-    pub async fn get_state(&self, api: Arc<ApiService>) -> Result<(u64, Option<String>)> {
+    pub async fn get_state(&self, api: Arc<ApiService>) -> Result<(u64, Option<Session>)> {
         let url = format!(
             "https://passport.bilibili.com/x/passport-login/web/qrcode/poll?qrcode_key={}",
             &self.key
         );
-        let res = api.get_json(&url).await?;
-        let res_code = res["data"]["code"].as_u64().unwrap();
-        if res_code == 0 {
-            let res_url = res["data"]["url"].as_str().unwrap();
-            let a = res_url.find("bili_jct=").expect("Can't find csrf data.");
-            let b = res_url[a..].find("&").unwrap();
-            let csrf = res_url[a + 9..b + a].to_string();
-            return Ok((res_code, Some(csrf)));
+        let data = api.fetch_api::<qrcode_poll::Data>(url).await?;
+        if data.code == 0 {
+            return Ok((
+                data.code,
+                Some(Session {
+                    cookie: cookie_from_url(&data.url),
+                    refresh_token: data.refresh_token,
+                }),
+            ));
         }
-        Ok((res_code, None))
+        Ok((data.code, None))
     }
 }
+
+/// 扫码成功后跳转地址的参数中带有登录 Cookie，拼接为 `ApiService::new` 可用的 Cookie 字符串
+fn cookie_from_url(url: &str) -> String {
+    const NAMES: [&str; 4] = ["DedeUserID", "DedeUserID__ckMd5", "SESSDATA", "bili_jct"];
+    let query = url.split_once('?').map(|(_, q)| q).unwrap_or_default();
+    let mut jar = CookieJar::default();
+    for (name, value) in query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(k, _)| NAMES.contains(k))
+    {
+        jar.set(name, value);
+    }
+    jar.to_string()
+}
@@ -0,0 +1,198 @@
+//! 按域名限速：每个域名一个令牌桶，遇到风控时自动降速，之后随正常的响应逐渐恢复
+
+use crate::types::BiliApiError;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::warn;
+
+/// 连续风控时最多降到原速率的 1/16
+const MAX_SLOWDOWN: f64 = 16.0;
+/// 每次正常的响应恢复一点速率
+const RECOVERY: f64 = 0.95;
+
+/// 令牌桶的速率
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    /// 每秒补充的令牌数，即平均每秒的请求数
+    pub per_second: f64,
+    /// 桶的容量，即空闲后允许连续发出的请求数
+    pub burst: u32,
+}
+
+impl Rate {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self {
+            per_second,
+            burst: burst.max(1),
+        }
+    }
+
+    /// 容量为一秒内的请求数
+    pub fn per_second(per_second: f64) -> Self {
+        Self::new(per_second, per_second.ceil() as u32)
+    }
+
+    /// 每 `seconds` 秒一个请求
+    pub fn every(seconds: f64) -> Self {
+        Self::new(1.0 / seconds, 1)
+    }
+}
+
+/// 各域名的速率，为 None 时不限速
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    /// api.bilibili.com，获取与删除评论、弹幕、通知
+    pub api: Option<Rate>,
+    /// message.bilibili.com，系统通知
+    pub message: Option<Rate>,
+    /// api.aicu.cc
+    pub aicu: Option<Rate>,
+    /// 删除请求在所在域名的限速之外另外限速，避免连续删除触发风控
+    pub delete: Option<Rate>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            api: Some(Rate::per_second(2.0)),
+            message: Some(Rate::per_second(2.0)),
+            aicu: Some(Rate::every(3.0)),
+            delete: Some(Rate::every(3.0)),
+        }
+    }
+}
+
+impl RateLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            api: None,
+            message: None,
+            aicu: None,
+            delete: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    /// 可以为负，表示已被预约的请求
+    tokens: f64,
+    last: Instant,
+    /// 风控后速率除以该值
+    slowdown: f64,
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: Rate,
+    state: Mutex<State>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            state: Mutex::new(State {
+                tokens: rate.burst as f64,
+                last: Instant::now(),
+                slowdown: 1.0,
+            }),
+        }
+    }
+
+    /// 按当前速率补充令牌
+    fn refill(&self, state: &mut State) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.current(state)).min(self.rate.burst as f64);
+        state.last = now;
+    }
+
+    fn current(&self, state: &State) -> f64 {
+        self.rate.per_second / state.slowdown
+    }
+
+    /// 取一个令牌，没有时等到轮到自己；先到的请求先发出
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            self.refill(&mut state);
+            state.tokens -= 1.0;
+            (state.tokens < 0.0).then(|| -state.tokens / self.current(&state))
+        };
+        if let Some(seconds) = wait {
+            sleep(Duration::from_secs_f64(seconds)).await;
+        }
+    }
+
+    /// 遇到风控：速率减半，并且清空积攒的令牌
+    pub fn throttle(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        state.slowdown = (state.slowdown * 2.0).min(MAX_SLOWDOWN);
+        state.tokens = state.tokens.min(0.0);
+        warn!(
+            "Rate limited, slowing down to {:.2} requests per second",
+            self.current(&state)
+        );
+    }
+
+    /// 正常的响应：逐渐恢复到配置的速率
+    pub fn recover(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.slowdown > 1.0 {
+            self.refill(&mut state);
+            state.slowdown = (state.slowdown * RECOVERY).max(1.0);
+        }
+    }
+
+    /// 当前速率是配置速率的几分之一
+    pub fn slowdown(&self) -> f64 {
+        self.state.lock().unwrap().slowdown
+    }
+
+    /// 根据 bilibili 返回的 code 调整速率
+    pub fn observe(&self, code: i64) {
+        if code == 0 {
+            self.recover();
+        } else if matches!(
+            BiliApiError::from_code(code, ""),
+            BiliApiError::RateLimited { .. }
+        ) {
+            self.throttle();
+        }
+    }
+}
+
+/// ApiService 使用的各域名的令牌桶
+#[derive(Debug)]
+pub struct Limiter {
+    pub api: Option<TokenBucket>,
+    pub message: Option<TokenBucket>,
+    pub aicu: Option<TokenBucket>,
+    /// 可在运行中调整
+    delete: RwLock<Option<Arc<TokenBucket>>>,
+}
+
+impl Limiter {
+    pub fn delete(&self) -> Option<Arc<TokenBucket>> {
+        self.delete.read().unwrap().clone()
+    }
+
+    /// 替换删除请求的限速，已在等待的请求不受影响
+    pub fn set_delete(&self, rate: Option<Rate>) {
+        *self.delete.write().unwrap() = rate.map(|r| Arc::new(TokenBucket::new(r)));
+    }
+}
+
+impl From<RateLimits> for Limiter {
+    fn from(limits: RateLimits) -> Self {
+        Self {
+            api: limits.api.map(TokenBucket::new),
+            message: limits.message.map(TokenBucket::new),
+            aicu: limits.aicu.map(TokenBucket::new),
+            delete: RwLock::new(limits.delete.map(|r| Arc::new(TokenBucket::new(r)))),
+        }
+    }
+}
@@ -35,6 +35,8 @@ pub mod danmu {
         pub content: String,
         #[serde(deserialize_with = "string_to_u64")]
         pub oid: u64,
+        #[serde(default)]
+        pub ctime: Option<u64>,
     }
 }
 
@@ -57,6 +59,8 @@ pub mod comment {
         #[serde(deserialize_with = "string_to_u64")]
         pub rpid: u64,
         pub message: String,
+        #[serde(default, alias = "ctime")]
+        pub time: Option<u64>,
         pub r#dyn: Dyn,
     }
     #[derive(Deserialize, Debug)]
@@ -1,4 +1,29 @@
+use crate::types::ApiFailure;
+use chrono::{FixedOffset, NaiveDateTime};
 use serde::Deserialize;
+use serde_json::Value;
+
+/// bilibili API 的通用响应
+#[derive(Deserialize, Debug)]
+pub struct Envelope<T = Value> {
+    pub code: i64,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub ttl: Option<i64>,
+    pub data: Option<T>,
+}
+
+impl<T> Envelope<T> {
+    /// code 不为 0 时返回对应的错误
+    pub fn into_result(self) -> Result<Option<T>, ApiFailure> {
+        if self.code == 0 {
+            Ok(self.data)
+        } else {
+            Err(ApiFailure::new(self.code, &self.message))
+        }
+    }
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Cursor {
@@ -19,12 +44,7 @@ pub mod like {
     use super::{Cursor, NestedDetail};
     use serde::Deserialize;
     #[derive(Deserialize, Debug)]
-    /// x/msgfeed/like
-    pub struct ApiResponse {
-        pub data: Data,
-    }
-
-    #[derive(Deserialize, Debug)]
+    /// x/msgfeed/like 的 data
     pub struct Data {
         pub total: Total,
     }
@@ -54,11 +74,7 @@ pub mod reply {
     use super::{Cursor, NestedDetail};
     use serde::Deserialize;
     #[derive(Deserialize, Debug)]
-    /// x/msgfeed/reply
-    pub struct ApiResponse {
-        pub data: Data,
-    }
-    #[derive(Deserialize, Debug)]
+    /// x/msgfeed/reply 的 data
     pub struct Data {
         pub cursor: Option<Cursor>,
         pub items: Vec<Item>,
@@ -69,6 +85,7 @@ pub mod reply {
         /// notify_id
         pub id: u64,
         pub item: ItemDetails,
+        pub reply_time: u64,
     }
     #[derive(Deserialize, Debug)]
     pub struct ItemDetails {
@@ -85,11 +102,7 @@ pub mod at {
     use super::{Cursor, NestedDetail};
     use serde::Deserialize;
     #[derive(Deserialize, Debug)]
-    /// x/msgfeed/at
-    pub struct ApiResponse {
-        pub data: Data,
-    }
-    #[derive(Deserialize, Debug)]
+    /// x/msgfeed/at 的 data
     pub struct Data {
         pub cursor: Option<Cursor>,
         pub items: Vec<Item>,
@@ -100,6 +113,7 @@ pub mod at {
         /// notify_id
         pub id: u64,
         pub item: ItemDetails,
+        pub at_time: u64,
     }
     #[derive(Deserialize, Debug)]
     pub struct ItemDetails {
@@ -118,10 +132,24 @@ pub struct SystemNotifyItem {
     pub item_type: u64, // Bilibili API often uses u64 for types that could be smaller
     pub title: String,
     pub content: String,
+    /// 如 `2024-01-01 12:00:00`，北京时间
+    #[serde(default)]
+    pub time_at: Option<String>,
     pub cursor: u64, // This cursor is present on each item, usually the same for a page,
                      // and the one from the last item is used for the next page.
 }
 
+impl SystemNotifyItem {
+    /// 解析 `time_at` 为时间戳
+    pub fn time(&self) -> Option<u64> {
+        let time_at = self.time_at.as_deref()?;
+        let datetime = NaiveDateTime::parse_from_str(time_at, "%Y-%m-%d %H:%M:%S").ok()?;
+        let beijing = FixedOffset::east_opt(8 * 3600)?;
+        let timestamp = datetime.and_local_timezone(beijing).single()?.timestamp();
+        u64::try_from(timestamp).ok()
+    }
+}
+
 // For the initial calls: query_user_notify, query_unified_notify
 #[derive(Deserialize, Debug)]
 pub struct InitialSystemNotifyData {
@@ -143,3 +171,212 @@ pub struct ContinuationSystemNotifyApiResponse {
     // message: String,
     pub data: Option<Vec<SystemNotifyItem>>, // Here, data is directly the list
 }
+
+pub mod space_dynamic {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/polymer/web-dynamic/v1/feed/space 的 data
+    pub struct Data {
+        #[serde(default)]
+        pub items: Vec<Item>,
+        pub has_more: bool,
+        /// 下一页的游标
+        #[serde(default)]
+        pub offset: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Item {
+        pub id_str: Option<String>,
+        pub basic: Basic,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Basic {
+        /// 评论区的 oid，没有评论区时为空
+        #[serde(default)]
+        pub comment_id_str: String,
+        /// 评论区的类型
+        #[serde(default)]
+        pub comment_type: u8,
+    }
+
+    impl Item {
+        /// 动态的评论区 (oid, type)
+        pub fn comment_area(&self) -> Option<(u64, u8)> {
+            let oid = self.basic.comment_id_str.parse().ok()?;
+            (self.basic.comment_type != 0).then_some((oid, self.basic.comment_type))
+        }
+    }
+}
+
+pub mod reply_list {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/v2/reply 与 x/v2/reply/reply 的 data
+    pub struct Data {
+        pub page: Page,
+        /// 没有评论时为 null
+        #[serde(default)]
+        pub replies: Option<Vec<Reply>>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Page {
+        pub num: u32,
+        pub size: u32,
+        /// 一级评论（或楼中楼）的总数
+        pub count: u64,
+    }
+
+    impl Page {
+        pub fn has_next(&self) -> bool {
+            u64::from(self.num) * u64::from(self.size) < self.count
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Reply {
+        pub rpid: u64,
+        /// 评论者 uid
+        pub mid: u64,
+        pub ctime: u64,
+        /// 楼中楼的总数
+        #[serde(default)]
+        pub rcount: u64,
+        pub content: Content,
+        /// 随一级评论一起返回的部分楼中楼
+        #[serde(default)]
+        pub replies: Option<Vec<Reply>>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Content {
+        pub message: String,
+    }
+}
+
+pub mod video_view {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/web-interface/view 的 data
+    pub struct Data {
+        pub title: String,
+        /// 番剧、影视的正片跳转到的播放页
+        #[serde(default)]
+        pub redirect_url: Option<String>,
+    }
+}
+
+pub mod dynamic_detail {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/polymer/web-dynamic/v1/detail 的 data
+    pub struct Data {
+        pub item: Item,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Item {
+        pub modules: Modules,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Modules {
+        pub module_dynamic: ModuleDynamic,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct ModuleDynamic {
+        #[serde(default)]
+        pub desc: Option<Text>,
+        #[serde(default)]
+        pub major: Option<Major>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Major {
+        /// 投稿视频的动态
+        #[serde(default)]
+        pub archive: Option<Titled>,
+        /// 图文动态
+        #[serde(default)]
+        pub opus: Option<Opus>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Titled {
+        pub title: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Opus {
+        #[serde(default)]
+        pub title: Option<String>,
+        #[serde(default)]
+        pub summary: Option<Text>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Text {
+        pub text: String,
+    }
+
+    impl Data {
+        /// 动态的标题，没有标题时为正文
+        pub fn title(self) -> Option<String> {
+            let ModuleDynamic { desc, major } = self.item.modules.module_dynamic;
+            let (archive, opus) = major.map_or((None, None), |m| (m.archive, m.opus));
+            archive
+                .map(|a| a.title)
+                .or_else(|| opus.and_then(|o| o.title.or(o.summary.map(|s| s.text))))
+                .or(desc.map(|d| d.text))
+                .filter(|t| !t.is_empty())
+        }
+    }
+}
+
+pub mod article_view {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/article/viewinfo 的 data
+    pub struct Data {
+        pub title: String,
+    }
+}
+
+pub mod member_account {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/member/web/account 的 data
+    pub struct Data {
+        pub mid: u64,
+        #[serde(default)]
+        pub uname: String,
+    }
+}
+
+pub mod qrcode_generate {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/passport-login/web/qrcode/generate 的 data
+    pub struct Data {
+        pub url: String,
+        pub qrcode_key: String,
+    }
+}
+
+pub mod qrcode_poll {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug)]
+    /// x/passport-login/web/qrcode/poll 的 data
+    pub struct Data {
+        /// 0 为已登录，86038 为二维码已失效，86090 为已扫码未确认，86101 为未扫码
+        pub code: u64,
+        /// 登录成功后的跳转地址，参数中带有 Cookie
+        #[serde(default)]
+        pub url: String,
+        #[serde(default)]
+        pub refresh_token: Option<String>,
+    }
+}
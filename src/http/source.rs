@@ -0,0 +1,398 @@
+//! 评论、弹幕、通知的来源
+//!
+//! 每个来源按页获取，游标由来源自己定义并序列化保存；新的来源实现 [`Source`] 后注册到 [`Registry`] 即可
+//!
+//! 请求的速率由 ApiService 统一限制，来源本身不需要等待
+
+use super::api_service::ApiService;
+use super::comment::{self, Comment};
+use super::danmu::{self, Danmu};
+use super::notify::{self, Notify, NotifyKey};
+use crate::screens::main;
+use crate::storage::{Checkpointer, ProgressStore, CHECKPOINT_INTERVAL};
+use crate::types::{AggregatedData, FetchProgressState, Message, Result};
+use iced::futures::channel::mpsc::Sender;
+use iced::futures::future::{join_all, BoxFuture};
+use iced::futures::SinkExt;
+use iced::{stream, Task};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// 游标对获取流程不透明，由来源自己解释
+pub type Cursor = serde_json::Value;
+
+/// 来源获取到的数据
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Items {
+    pub notify: HashMap<NotifyKey, Notify>,
+    pub comments: HashMap<u64, Comment>,
+    pub danmu: HashMap<u64, Danmu>,
+}
+
+impl Items {
+    pub fn len(&self) -> usize {
+        self.notify.len() + self.comments.len() + self.danmu.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn extend(&mut self, other: Items) {
+        self.notify.extend(other.notify);
+        self.comments.extend(other.comments);
+        self.danmu.extend(other.danmu);
+    }
+}
+
+/// 一页的结果
+#[derive(Debug, Default)]
+pub struct Page {
+    pub items: Items,
+    /// 下一页的游标，为 None 时已获取完
+    pub next: Option<Cursor>,
+}
+
+impl Page {
+    /// 最后一页
+    pub fn end(items: Items) -> Self {
+        Self { items, next: None }
+    }
+
+    /// 还有下一页
+    pub fn next(items: Items, cursor: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            items,
+            next: Some(serde_json::to_value(cursor)?),
+        })
+    }
+}
+
+/// 将保存的游标还原为来源自己的类型
+pub fn decode_cursor<T: DeserializeOwned>(cursor: Option<Cursor>) -> Result<Option<T>> {
+    Ok(cursor.map(serde_json::from_value).transpose()?)
+}
+
+pub trait Source: Send + Sync {
+    /// 保存进度与设置时使用，不能修改
+    fn id(&self) -> &'static str;
+
+    /// 界面中显示的名称
+    fn name(&self) -> &'static str;
+
+    /// 获取 cursor 处的一页，cursor 为 None 时从第一页开始
+    fn fetch_page<'a>(
+        &'a self,
+        api: &'a Arc<ApiService>,
+        cursor: Option<Cursor>,
+    ) -> BoxFuture<'a, Result<Page>>;
+}
+
+/// 按顺序获取的来源，以及各自是否启用
+#[derive(Clone)]
+pub struct Registry {
+    sources: Vec<(Arc<dyn Source>, bool)>,
+}
+
+impl Registry {
+    pub fn empty() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    /// 添加到末尾，默认启用；同一条数据以先注册的来源为准
+    pub fn register(&mut self, source: impl Source + 'static) {
+        self.sources.push((Arc::new(source), true));
+    }
+
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) {
+        if let Some((_, e)) = self.sources.iter_mut().find(|(s, _)| s.id() == id) {
+            *e = enabled;
+        }
+    }
+
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.sources.iter().any(|(s, e)| *e && s.id() == id)
+    }
+
+    /// 登录界面中的 aicu.cc 开关
+    pub fn with_aicu(mut self, enabled: bool) -> Self {
+        for id in [comment::aicu::ID, danmu::aicu::ID] {
+            self.set_enabled(id, enabled);
+        }
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&dyn Source, bool)> {
+        self.sources.iter().map(|(s, e)| (s.as_ref(), *e))
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &dyn Source> {
+        self.iter().filter(|(_, e)| *e).map(|(s, _)| s)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(notify::Liked);
+        registry.register(notify::Replyed);
+        registry.register(notify::Ated);
+        registry.register(notify::SystemNotify);
+        registry.register(comment::dynamic::DynamicComments);
+        registry.register(comment::aicu::AicuComments);
+        registry.register(danmu::aicu::AicuDanmu);
+        registry
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.sources.iter().map(|(s, e)| (s.id(), e)))
+            .finish()
+    }
+}
+
+/// 一个来源的获取进度
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SourceProgress {
+    pub items: Items,
+    /// 下一页的游标，从头开始时为 None
+    pub cursor: Option<Cursor>,
+    pub pages: u32,
+    pub done: bool,
+}
+
+impl SourceProgress {
+    pub fn status(&self) -> Status {
+        Status {
+            pages: self.pages,
+            items: self.items.len(),
+            done: self.done,
+            failed: false,
+        }
+    }
+}
+
+/// 界面中显示的来源进度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Status {
+    pub pages: u32,
+    pub items: usize,
+    pub done: bool,
+    /// 下一页获取失败，等待继续
+    pub failed: bool,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.done {
+            write!(f, "done, {} items", self.items)
+        } else if self.failed {
+            write!(
+                f,
+                "interrupted at page {}, {} items",
+                self.pages + 1,
+                self.items
+            )
+        } else if self.pages == 0 {
+            f.write_str("waiting")
+        } else {
+            write!(f, "page {}, {} items", self.pages, self.items)
+        }
+    }
+}
+
+/// 同时获取所有启用的来源，每隔 [`CHECKPOINT_INTERVAL`] 保存一次进度
+///
+/// 有来源失败时，其余来源仍会获取完，然后返回 `(None, Some(进度))`，下次从各自失败的那页继续；全部完成后返回合并的数据
+pub async fn fetch(
+    api: Arc<ApiService>,
+    sources: Registry,
+    progress_state: FetchProgressState,
+    report: impl FnMut(&'static str, Status) + Send,
+) -> Result<(Option<Arc<AggregatedData>>, Option<FetchProgressState>)> {
+    let store = match api
+        .get_uid()
+        .await
+        .and_then(|uid| ProgressStore::new(&api.data_dir()?, uid))
+    {
+        Ok(store) => Some(store),
+        Err(e) => {
+            warn!("Fetch progress won't be persisted: {}", e);
+            None
+        }
+    };
+    // 本地进度在中断时一定会保存，不会比传入的旧
+    let mut state = store
+        .as_ref()
+        .and_then(ProgressStore::load)
+        .unwrap_or(progress_state);
+    // 关闭的来源再次启用时重新获取
+    state.sources.retain(|id, _| sources.is_enabled(id));
+
+    let run = Run {
+        api: &api,
+        checkpoints: Checkpointer::new(store.clone(), CHECKPOINT_INTERVAL),
+        state: Mutex::new(state),
+        report: Mutex::new(report),
+        mp: MultiProgress::new(),
+    };
+    let completed = join_all(sources.enabled().map(|source| run.source(source))).await;
+    let state = run.state.into_inner();
+    if completed.contains(&false) {
+        run.checkpoints
+            .write(run.checkpoints.snapshot(&state, true));
+        return Ok((None, Some(state)));
+    }
+
+    let data = aggregate(&state, &sources);
+    if let Some(store) = &store {
+        store.clear();
+    }
+    Ok((Some(Arc::new(data)), None))
+}
+
+/// 一次获取中各来源共享的状态
+struct Run<'a, F> {
+    api: &'a Arc<ApiService>,
+    checkpoints: Checkpointer,
+    state: Mutex<FetchProgressState>,
+    report: Mutex<F>,
+    mp: MultiProgress,
+}
+
+impl<F: FnMut(&'static str, Status) + Send> Run<'_, F> {
+    /// 获取一个来源直到完成，返回是否完成
+    async fn source(&self, source: &dyn Source) -> bool {
+        let id = source.id();
+        let status = self
+            .state
+            .lock()
+            .await
+            .sources
+            .entry(id.to_string())
+            .or_default()
+            .status();
+        (*self.report.lock().await)(id, status);
+        if status.done {
+            info!("Skipping {}, already fetched.", source.name());
+            return true;
+        }
+        info!("Fetching {} (resuming if applicable)...", source.name());
+
+        let pb = self.mp.add(ProgressBar::new_spinner());
+        pb.set_style(
+            ProgressStyle::with_template("[{elapsed_precise}] {spinner:.green} {msg}").unwrap(),
+        );
+        pb.enable_steady_tick(Duration::from_millis(100));
+        pb.set_message(format!("{}. Counts: {}", source.name(), status.items));
+
+        loop {
+            let cursor = self.state.lock().await.sources[id].cursor.clone();
+            let page = source.fetch_page(self.api, cursor).await;
+
+            let mut state = self.state.lock().await;
+            let progress = state.sources.get_mut(id).expect("inserted above");
+            let status = match page {
+                Ok(page) => {
+                    progress.items.extend(page.items);
+                    progress.pages += 1;
+                    progress.done = page.next.is_none();
+                    progress.cursor = page.next;
+                    progress.status()
+                }
+                Err(e) => {
+                    warn!(
+                        "Error fetching {} page: {}. Will attempt to resume later.",
+                        source.name(),
+                        e
+                    );
+                    Status {
+                        failed: true,
+                        ..progress.status()
+                    }
+                }
+            };
+            // 重启后从保存的那页继续；在锁外写入
+            let snapshot = self.checkpoints.snapshot(&state, false);
+            drop(state);
+            self.checkpoints.write(snapshot);
+            (*self.report.lock().await)(id, status);
+
+            if status.failed {
+                pb.abandon_with_message(format!(
+                    "{}: Error. Counts: {}",
+                    source.name(),
+                    status.items
+                ));
+                return false;
+            }
+            if status.done {
+                pb.finish_with_message(format!(
+                    "{}: Done. Counts: {}",
+                    source.name(),
+                    status.items
+                ));
+                info!("{} fetched completely.", source.name());
+                return true;
+            }
+            pb.set_message(format!(
+                "{}: Page {}. Counts: {}",
+                source.name(),
+                status.pages,
+                status.items
+            ));
+        }
+    }
+}
+
+/// 同一条数据以先注册的来源为准，如带通知的评论
+fn aggregate(state: &FetchProgressState, sources: &Registry) -> AggregatedData {
+    fn merge<K: Copy + Eq + Hash, T: Clone>(into: &mut HashMap<K, T>, from: &HashMap<K, T>) {
+        for (id, item) in from {
+            into.entry(*id).or_insert_with(|| item.clone());
+        }
+    }
+
+    let mut data = AggregatedData::default();
+    for source in sources.enabled() {
+        if let Some(progress) = state.sources.get(source.id()) {
+            merge(data.entry::<Notify>(), &progress.items.notify);
+            merge(data.entry::<Comment>(), &progress.items.comments);
+            merge(data.entry::<Danmu>(), &progress.items.danmu);
+        }
+    }
+    data
+}
+
+/// 在界面中获取，每页报告一次各来源的进度
+pub fn fetch_task(
+    api: Arc<ApiService>,
+    sources: Registry,
+    progress_state: FetchProgressState,
+) -> Task<Message> {
+    Task::run(
+        stream::channel(100, |mut output: Sender<Message>| async move {
+            let mut progress = output.clone();
+            let res = fetch(api, sources, progress_state, move |id, status| {
+                let _ = progress.try_send(Message::Main(main::Message::SourceProgress(id, status)));
+            })
+            .await;
+            let _ = output
+                .send(Message::Main(main::Message::Fetched(res)))
+                .await;
+        }),
+        |m| m,
+    )
+}
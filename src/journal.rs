@@ -0,0 +1,131 @@
+//! 删除记录：每次删除尝试追加一行 JSON，便于事后查看，之后运行时跳过同一账号已删除的项
+
+use crate::http::notify::Feed;
+use crate::storage::data_dir;
+use crate::types::{BiliApiError, Error, ItemKey, Result};
+use crate::Type;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::{error, warn};
+
+/// 一次删除尝试
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub kind: Type,
+    pub id: u64,
+    /// 删除时登录的账号，旧版记录没有这一项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u64>,
+    /// 通知所在的列表，旧版记录没有这一项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    /// 本地时间，RFC 3339
+    pub at: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 删除失败时归类后的错误
+    #[serde(default)]
+    pub api_error: Option<BiliApiError>,
+    /// 删除失败时 bilibili 返回的原始 code 与 message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// 被删除的评论、弹幕或通知本身，包含 oid / cid / content 等
+    pub item: Value,
+}
+
+impl Record {
+    pub fn new<K: ItemKey, T: Serialize>(
+        kind: Type,
+        uid: Option<u64>,
+        key: K,
+        item: &T,
+        result: &Result<K>,
+    ) -> Self {
+        let (error, failure) = match result {
+            Ok(_) => (None, None),
+            Err(e) => (Some(e.to_string()), e.api_failure().cloned()),
+        };
+        Record {
+            kind,
+            id: key.id(),
+            uid,
+            feed: key.feed(),
+            at: Local::now().to_rfc3339(),
+            ok: result.is_ok(),
+            error,
+            api_error: failure.as_ref().map(|f| f.error.clone()),
+            code: failure.as_ref().map(|f| f.code),
+            message: failure.map(|f| f.message),
+            item: serde_json::to_value(item).unwrap_or_default(),
+        }
+    }
+}
+
+/// 只追加的删除记录文件
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// 数据目录下的 `journal.jsonl`
+    pub fn open() -> Result<Self> {
+        Self::open_in(&data_dir()?)
+    }
+
+    pub fn open_in(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            path: dir.join("journal.jsonl"),
+        })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// 写入失败只记录日志，不影响删除
+    pub fn append(&self, record: &Record) {
+        let res = serde_json::to_string(record)
+            .map_err(Error::from)
+            .and_then(|line| {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                writeln!(file, "{line}")?;
+                Ok(())
+            });
+        if let Err(e) = res {
+            error!("Failed to write deletion journal: {}", e);
+        }
+    }
+
+    /// 账号 `uid` 已成功删除的项；其他账号的 id 可能相同，不算在内
+    pub fn deleted_keys<K: ItemKey>(&self, kind: Type, uid: Option<u64>) -> HashSet<K> {
+        // 不知道账号时不跳过任何项
+        let (Some(_), Ok(file)) = (uid, File::open(&self.path)) else {
+            return HashSet::new();
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(|line| line.ok())
+            .filter_map(|line| match serde_json::from_str::<Record>(&line) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    warn!("Skipping unreadable journal line: {}", e);
+                    None
+                }
+            })
+            .filter(|r| r.ok && r.kind == kind && r.uid == uid)
+            .filter_map(|r| K::from_parts(r.id, r.feed))
+            .collect()
+    }
+}
@@ -1,36 +1,58 @@
 use http::api_service::ApiService;
 use iced::futures::channel::mpsc::Sender;
+use iced::futures::future::BoxFuture;
 use iced::futures::SinkExt;
 use iced::{stream, Subscription};
 use indicatif::{ProgressBar, ProgressStyle};
+use journal::{Journal, Record};
+use retry::RetryPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::spawn;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
-use tracing::error;
+use tracing::{error, info, warn};
 
+pub mod archive;
+pub mod browser;
+pub mod dry_run;
+pub mod filter;
 pub mod http;
+pub mod journal;
+pub mod retry;
 pub mod screens;
+pub mod storage;
 pub mod types;
-pub use crate::screens::main::comment_viewer::CvMsg as cvmsg;
-pub use crate::screens::main::danmu_viewer::DvMsg as dvmsg;
-pub use crate::screens::main::notify_viewer::NvMsg as nvmsg;
+pub use crate::screens::main::item_viewer::ViewerMsg;
 
 use crate::screens::main;
-use crate::types::{ChannelMsg, Message, RemoveAble};
+use crate::types::{BiliApiError, ChannelMsg, Deletable, Item, Message};
 
 const UA:&str="Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/127.0.0.0 Safari/537.36 Edg/127.0.2651.86";
 
-enum Type {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Type {
     Comment,
     Danmu,
     Notify,
 }
 
+impl Type {
+    /// 英文名称，用作查看器的标题
+    pub fn id(&self) -> &'static str {
+        match self {
+            Type::Comment => "comment",
+            Type::Danmu => "danmu",
+            Type::Notify => "notify",
+        }
+    }
+}
+
 impl Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -41,30 +63,55 @@ impl Display for Type {
     }
 }
 
-async fn handle_delete<T>(
+pub async fn handle_delete<T>(
     delete_flag: Arc<AtomicBool>,
     mut output: Sender<Message>,
-    items: Vec<(u64, T)>,
-    tp: Type,
-    seconds: f32,
+    items: Vec<(T::Key, T)>,
+    retry: RetryPolicy,
     api: Arc<ApiService>,
 ) where
-    T: RemoveAble,
+    T: Item,
 {
     if items.is_empty() {
         return;
     }
 
+    let tp = T::TYPE;
+    let msg_done: Message = ViewerMsg::<T>::AllDeleted.into();
+
+    let journal = match api.data_dir().and_then(|dir| Journal::open_in(&dir)) {
+        Ok(j) => Some(j),
+        Err(e) => {
+            error!("Deletion journal unavailable: {}", e);
+            None
+        }
+    };
+    let uid = api.session().uid();
+    // 之前已删除过的直接从界面移除，不再发送请求
+    let deleted = journal
+        .as_ref()
+        .map(|j| j.deleted_keys::<T::Key>(tp, uid))
+        .unwrap_or_default();
+    let (skipped, items): (Vec<_>, Vec<_>) =
+        items.into_iter().partition(|(id, _)| deleted.contains(id));
+    if !skipped.is_empty() {
+        info!("跳过 {} 条已删除的{}", skipped.len(), tp);
+    }
+    for (id, _) in skipped {
+        output
+            .send(ViewerMsg::<T>::Deleted { key: id }.into())
+            .await
+            .unwrap();
+    }
+    if items.is_empty() {
+        output.send(msg_done).await.unwrap();
+        return;
+    }
+
     let len = items.len();
     let pb = ProgressBar::new(len as u64);
     pb.set_style(ProgressStyle::with_template("{wide_bar} {pos}/{len} {msg}").unwrap());
 
-    let msg_done: Message = match tp {
-        Type::Comment => cvmsg::AllCommentDeleted.into(),
-        Type::Danmu => dvmsg::AllDanmuDeleted.into(),
-        Type::Notify => nvmsg::AllNotifyDeleted.into(),
-    };
-
     for (index, item) in items.into_iter().enumerate() {
         let (id, data) = item;
         if !delete_flag.load(Ordering::SeqCst) {
@@ -72,14 +119,41 @@ async fn handle_delete<T>(
             output.send(msg_done.clone()).await.unwrap();
             break;
         }
-        match data.remove(id, api.clone()).await {
+        // 逐步删除，失败时只重试失败的那一步
+        let (mut step, mut attempt) = (0, 1);
+        let res = loop {
+            if step == data.steps() {
+                break Ok(id);
+            }
+            match data.remove_step(id, step, api.clone()).await {
+                Ok(()) => (step, attempt) = (step + 1, 1),
+                // 已经被删除的视为这一步成功，继续删除随之的通知
+                Err(e) if e.api_error() == Some(&BiliApiError::AlreadyDeleted) => {
+                    (step, attempt) = (step + 1, 1)
+                }
+                Err(e) if retry.should_retry(attempt, &e) && delete_flag.load(Ordering::SeqCst) => {
+                    let delay = retry.delay(attempt);
+                    warn!(
+                        "删除{} {} 失败，{:.1} 秒后重试（第 {} 次）：{}",
+                        tp,
+                        id,
+                        delay.as_secs_f32(),
+                        attempt,
+                        e
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        if let Some(journal) = &journal {
+            journal.append(&Record::new(tp, uid, id, &data, &res));
+        }
+        match res {
             Ok(id) => {
                 output
-                    .send(match tp {
-                        Type::Comment => cvmsg::CommentDeleted { rpid: id }.into(),
-                        Type::Danmu => dvmsg::DanmuDeleted { id }.into(),
-                        Type::Notify => nvmsg::NotifyDeleted { id }.into(),
-                    })
+                    .send(ViewerMsg::<T>::Deleted { key: id }.into())
                     .await
                     .unwrap();
                 pb.set_message(format!("已删除{}：{}", tp, id));
@@ -87,6 +161,22 @@ async fn handle_delete<T>(
             }
             Err(err) => {
                 error!("{}", err);
+                output
+                    .send(
+                        ViewerMsg::<T>::DeleteFailed {
+                            key: id,
+                            error: err.to_string(),
+                        }
+                        .into(),
+                    )
+                    .await
+                    .unwrap();
+                // 登录失效，之后的请求都会失败
+                if err.api_error().is_some_and(BiliApiError::is_auth) {
+                    output.send(msg_done.clone()).await.unwrap();
+                    pb.abandon_with_message("登录失效，已停止删除");
+                    break;
+                }
             }
         }
 
@@ -94,7 +184,26 @@ async fn handle_delete<T>(
             output.send(msg_done.clone()).await.unwrap();
             pb.finish_with_message("删除完成");
         }
-        sleep(Duration::from_secs_f32(seconds)).await;
+    }
+}
+
+impl<T: Item> Deletable for Vec<(T::Key, T)> {
+    fn tp(&self) -> Type {
+        T::TYPE
+    }
+
+    fn delete(
+        self: Box<Self>,
+        flag: Arc<AtomicBool>,
+        output: Sender<Message>,
+        retry: RetryPolicy,
+        api: Arc<ApiService>,
+    ) -> BoxFuture<'static, ()> {
+        Box::pin(handle_delete(flag, output, *self, retry, api))
+    }
+
+    fn dry_run(self: Box<Self>) -> BoxFuture<'static, types::Result<dry_run::Summary>> {
+        Box::pin(async move { dry_run::run(&self, T::TYPE) })
     }
 }
 
@@ -107,92 +216,25 @@ pub fn main_subscription() -> Subscription<Message> {
                 .await
                 .unwrap();
 
-            let flags = (
-                Arc::new(AtomicBool::new(false)), // QR code
-                Arc::new(AtomicBool::new(true)),  // Comment
-                Arc::new(AtomicBool::new(true)),  // Notify
-                Arc::new(AtomicBool::new(true)),  // Danmu
-            );
-
-            let mut tasks: (
-                Option<JoinHandle<()>>,
-                Option<JoinHandle<()>>,
-                Option<JoinHandle<()>>,
-            ) = (None, None, None);
+            // 各类项的删除任务与停止标志
+            let mut flags: HashMap<Type, Arc<AtomicBool>> = HashMap::new();
+            let mut tasks: HashMap<Type, JoinHandle<()>> = HashMap::new();
 
             while let Some(msg) = receiver.recv().await {
                 match msg {
-                    ChannelMsg::DeleteComment(api, c, seconds) => {
-                        let comments = c
-                            .lock()
-                            .await
-                            .iter()
-                            .filter(|e| e.1.is_selected)
-                            .map(|(a, b)| (*a, b.clone()))
-                            .collect::<Vec<_>>();
-
-                        let flag = Arc::clone(&flags.1);
-                        let output_clone = output.clone();
-                        let task = spawn(handle_delete(
-                            flag,
-                            output_clone,
-                            comments,
-                            Type::Comment,
-                            seconds,
-                            api.clone(),
-                        ));
-                        tasks.0 = Some(task);
-                    }
-                    ChannelMsg::StopDeleteComment => {
-                        flags.1.store(false, Ordering::SeqCst);
-                    }
-                    ChannelMsg::DeleteNotify(api, c, seconds) => {
-                        let notify = c
-                            .lock()
-                            .await
-                            .iter()
-                            .filter(|e| e.1.is_selected)
-                            .map(|(a, b)| (*a, b.clone()))
-                            .collect::<Vec<_>>();
-
-                        let flag = Arc::clone(&flags.2);
-                        let output_clone = output.clone();
-                        let task = spawn(handle_delete(
-                            flag,
-                            output_clone,
-                            notify,
-                            Type::Notify,
-                            seconds,
-                            api.clone(),
-                        ));
-                        tasks.1 = Some(task);
-                    }
-                    ChannelMsg::StopDeleteNotify => {
-                        flags.2.store(false, Ordering::SeqCst);
-                    }
-                    ChannelMsg::DeleteDanmu(api, c, seconds) => {
-                        let danmu = c
-                            .lock()
-                            .await
-                            .iter()
-                            .filter(|e| e.1.is_selected)
-                            .map(|(a, b)| (*a, b.clone()))
-                            .collect::<Vec<_>>();
-
-                        let flag = Arc::clone(&flags.3);
-                        let output_clone = output.clone();
-                        let task = spawn(handle_delete(
-                            flag,
-                            output_clone,
-                            danmu,
-                            Type::Danmu,
-                            seconds,
-                            api.clone(),
-                        ));
-                        tasks.2 = Some(task);
+                    ChannelMsg::Delete(api, items, retry) => {
+                        let tp = items.tp();
+                        let flag = flags
+                            .entry(tp)
+                            .or_insert_with(|| Arc::new(AtomicBool::new(true)));
+                        let task =
+                            spawn(items.delete(Arc::clone(flag), output.clone(), retry, api));
+                        tasks.insert(tp, task);
                     }
-                    ChannelMsg::StopDeleteDanmu => {
-                        flags.3.store(false, Ordering::SeqCst);
+                    ChannelMsg::StopDelete(tp) => {
+                        if let Some(flag) = flags.get(&tp) {
+                            flag.store(false, Ordering::SeqCst);
+                        }
                     }
                 }
             }
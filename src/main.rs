@@ -1,15 +1,21 @@
-use bilibili_comment_cleaning::http::{api_service::ApiService, notify, qr_code::QRdata};
+use bilibili_comment_cleaning::http::{
+    api_service::ApiService, meta, qr_code::QRdata, rate_limit::Rate, source,
+};
 use bilibili_comment_cleaning::{
     main_subscription,
     screens::{cookie, main, qrcode, Screen},
+    storage::{data_dir, AccountStore},
     types::*,
 };
-use iced::{time, Element, Subscription, Task};
+use iced::widget::{button, column, pick_list, row, text, text_input, Space};
+use iced::{task, time, Alignment, Element, Length, Subscription, Task};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::spawn;
 use tokio::sync::mpsc::Sender;
+use tracing::{error, warn};
 use tracing_subscriber::fmt::time::LocalTime;
 
 static TAFFY: &[u8] = include_bytes!("assets/taffy.png");
@@ -40,23 +46,63 @@ struct App {
     screen: Screen,
     sender: Option<Sender<ChannelMsg>>,
     aicu_state: Arc<AtomicBool>,
+    fetch_handle: Option<task::Handle>,
+    /// 保存的账号，最近使用的在前
+    accounts: Vec<Account>,
+    account_store: Option<AccountStore>,
+    /// 界面中输入的保存账号的口令
+    passphrase: String,
+    /// 无法读取或保存账号的原因
+    account_error: Option<String>,
+    /// 当前账号的 uid，获取到账号信息前为 None
+    current: Option<u64>,
+    /// 刚登录、尚未保存的登录凭据
+    pending_session: Option<Session>,
+    /// 切换出去的账号，保留各自的界面和获取进度
+    parked: HashMap<u64, (Arc<ApiService>, main::Main)>,
 }
 
 impl App {
     fn new() -> (Self, Task<Message>) {
         let aicu_state = Arc::new(AtomicBool::new(true));
-        let app = App {
+        // 无法解密已保存的账号时不使用，避免保存时覆盖
+        let (account_store, accounts, account_error) =
+            match AccountStore::open().and_then(|store| Ok((store.clone(), store.load()?))) {
+                Ok((store, accounts)) => (Some(store), accounts, None),
+                Err(Error::NoPassphrase) => (None, Vec::new(), None),
+                Err(e) => {
+                    error!("Accounts won't be saved: {}", e);
+                    (None, Vec::new(), Some(e.to_string()))
+                }
+            };
+        let mut app = App {
             api: Arc::new(ApiService::default()),
             screen: Screen::new(aicu_state.clone()),
             sender: None,
             aicu_state,
+            fetch_handle: None,
+            accounts,
+            account_store,
+            passphrase: String::new(),
+            account_error,
+            current: None,
+            pending_session: None,
+            parked: HashMap::new(),
+        };
+        // 最近使用的账号的登录仍有效时跳过扫码
+        let task = match app.accounts.first() {
+            Some(account) => match ApiService::from_session(account.session()) {
+                Ok(api) => {
+                    // 已过期的 Cookie 会在验证时刷新
+                    app.api = Arc::new(app.with_store(api));
+                    let api = app.api.clone();
+                    Task::perform(async move { api.get_uid().await }, Message::SessionChecked)
+                }
+                Err(_) => request_qrcode(app.api.clone()),
+            },
+            None => request_qrcode(app.api.clone()),
         };
-        (
-            app,
-            Task::perform(QRdata::request_qrcode(), |a| {
-                Message::QRCode(qrcode::Message::QRcodeGot(a))
-            }),
-        )
+        (app, task)
     }
 
     fn update(&mut self, msg: Message) -> Task<Message> {
@@ -66,24 +112,23 @@ impl App {
                     match c.update(msg) {
                         cookie::Action::Run(t) => t.map(Message::Cookie),
                         cookie::Action::EnterQRCode => {
-                            let (s, t) = qrcode::QRCode::new(self.aicu_state.clone());
-                            self.screen = Screen::WaitScanQRcode(s);
-                            t.map(Message::QRCode)
-                        }
-                        cookie::Action::Boot { api, aicu_state } => {
-                            self.api = Arc::new(api);
-                            self.screen = Screen::Main(main::Main::new(aicu_state));
-
-                            if let Screen::Main(ref m) = self.screen {
-                                fetch_task(
-                                    self.api.clone(),
-                                    self.aicu_state.load(Ordering::SeqCst),
-                                    m.progress.clone(),
-                                )
-                            } else {
-                                Task::none()
-                            }
+                            self.screen = Screen::WaitScanQRcode(qrcode::QRCode::new(
+                                self.aicu_state.clone(),
+                            ));
+                            request_qrcode(self.api.clone())
                         }
+                        cookie::Action::Boot {
+                            api,
+                            cookie,
+                            aicu_state,
+                        } => self.boot(
+                            api,
+                            Session {
+                                cookie,
+                                refresh_token: None,
+                            },
+                            aicu_state,
+                        ),
                         cookie::Action::None => Task::none(),
                     }
                 } else {
@@ -98,23 +143,16 @@ impl App {
                             self.send_to_channel(m);
                             Task::none()
                         }
-                        qrcode::Action::Boot { csrf, aicu_state } => {
-                            self.api = Arc::new(ApiService::new_with_fields(
-                                self.api.client().clone(),
-                                csrf,
-                            ));
-                            self.screen = Screen::Main(main::Main::new(aicu_state));
-
-                            if let Screen::Main(ref m) = self.screen {
-                                fetch_task(
-                                    self.api.clone(),
-                                    self.aicu_state.load(Ordering::SeqCst),
-                                    m.progress.clone(),
-                                )
-                            } else {
+                        qrcode::Action::Boot {
+                            session,
+                            aicu_state,
+                        } => match ApiService::from_session(session.clone()) {
+                            Ok(api) => self.boot(api, session, aicu_state),
+                            Err(e) => {
+                                error!("{}", e);
                                 Task::none()
                             }
-                        }
+                        },
                         qrcode::Action::EnterCookie => {
                             self.screen = Screen::WaitingForInputCookie(cookie::Cookie::new(
                                 self.aicu_state.clone(),
@@ -138,78 +176,215 @@ impl App {
                 }
             }
             Message::Main(msg) => {
-                if let Screen::Main(m) = &mut self.screen {
+                let task = if let Screen::Main(m) = &mut self.screen {
                     match m.update(msg) {
                         main::Action::Run(t) => t.map(Message::Main),
                         main::Action::SendtoChannel(m) => {
                             self.send_to_channel(m);
                             Task::none()
                         }
-                        main::Action::DeleteComment {
-                            comments,
-                            sleep_seconds,
-                        } => {
-                            self.send_to_channel(ChannelMsg::DeleteComment(
+                        main::Action::Delete { items } => {
+                            let retry = m.retry;
+                            self.api
+                                .set_delete_rate(Some(Rate::every(m.delete_interval.into())));
+                            self.send_to_channel(ChannelMsg::Delete(
                                 self.api.clone(),
-                                comments,
-                                sleep_seconds,
+                                items,
+                                retry,
                             ));
                             Task::none()
                         }
-
-                        main::Action::DeleteNotify {
-                            notify,
-                            sleep_seconds,
-                        } => {
-                            self.send_to_channel(ChannelMsg::DeleteNotify(
-                                self.api.clone(),
-                                notify,
-                                sleep_seconds,
-                            ));
-                            Task::none()
-                        }
-                        main::Action::DeleteDanmu {
-                            danmu,
-                            sleep_seconds,
-                        } => {
-                            self.send_to_channel(ChannelMsg::DeleteDanmu(
-                                self.api.clone(),
-                                danmu,
-                                sleep_seconds,
-                            ));
-                            Task::none()
-                        }
-                        main::Action::RetryFetch => {
-                            if let Screen::Main(ref m) = self.screen {
-                                fetch_task(
-                                    self.api.clone(),
-                                    self.aicu_state.load(Ordering::SeqCst),
-                                    m.progress.clone(),
-                                )
-                            } else {
-                                Task::none()
+                        main::Action::RetryFetch => self.start_fetch(),
+                        main::Action::AbortFetch => {
+                            if let Some(handle) = self.fetch_handle.take() {
+                                handle.abort();
                             }
+                            Task::none()
                         }
                         main::Action::None => Task::none(),
                     }
                 } else {
                     Task::none()
-                }
+                };
+                Task::batch([task, self.resolve_parents()])
             }
             Message::ChannelConnected(sender) => {
                 self.sender = Some(sender);
                 Task::none()
             }
+            Message::SessionChecked(res) => {
+                // 验证期间已手动登录
+                if let Screen::Main(_) = self.screen {
+                    return Task::none();
+                }
+                match (res, self.accounts.first().cloned()) {
+                    (Ok(uid), Some(mut account)) if uid == account.uid => {
+                        let session = self.api.session();
+                        account.cookie = session.cookie;
+                        account.refresh_token = session.refresh_token;
+                        self.login_saved(account)
+                    }
+                    (res, _) => {
+                        if let Err(e) = res {
+                            warn!("Saved session is no longer valid: {}", e);
+                        }
+                        self.api = Arc::new(ApiService::default());
+                        request_qrcode(self.api.clone())
+                    }
+                }
+            }
+            Message::AccountLoaded(Ok((uid, nickname))) => {
+                // 登录后已经切换走的账号不再处理
+                if let Some(session) = self.pending_session.take() {
+                    // 重新登录了切换出去的账号
+                    self.parked.remove(&uid);
+                    self.current = Some(uid);
+                    self.accounts.retain(|a| a.uid != uid);
+                    self.accounts.insert(
+                        0,
+                        Account {
+                            uid,
+                            nickname,
+                            cookie: session.cookie,
+                            refresh_token: session.refresh_token,
+                        },
+                    );
+                    self.save_accounts();
+                }
+                Task::none()
+            }
+            Message::AccountLoaded(Err(e)) => {
+                error!("Failed to get account info: {}", e);
+                Task::none()
+            }
+            Message::SwitchAccount(uid) => {
+                if self.current == Some(uid) || !self.can_switch() {
+                    return Task::none();
+                }
+                let Some(account) = self.accounts.iter().find(|a| a.uid == uid).cloned() else {
+                    return Task::none();
+                };
+                self.park();
+                match self.parked.remove(&uid) {
+                    Some((api, m)) => {
+                        let has_data = m.has_data();
+                        self.api = api;
+                        self.screen = Screen::Main(m);
+                        self.current = Some(uid);
+                        self.accounts.retain(|a| a.uid != uid);
+                        self.accounts.insert(0, account);
+                        self.save_accounts();
+                        if has_data {
+                            Task::none()
+                        } else {
+                            self.start_fetch()
+                        }
+                    }
+                    None => self.login_saved(account),
+                }
+            }
+            Message::PassphraseChanged(passphrase) => {
+                self.passphrase = passphrase;
+                Task::none()
+            }
+            Message::UnlockAccounts => {
+                let passphrase = std::mem::take(&mut self.passphrase);
+                Task::perform(
+                    async move {
+                        // 派生密钥较慢，不占用界面的运行时线程
+                        tokio::task::spawn_blocking(move || {
+                            let store = AccountStore::open_in(&data_dir()?, &passphrase)?;
+                            let accounts = store.load()?;
+                            Ok((store, accounts))
+                        })
+                        .await
+                        .unwrap_or(Err(Error::SessionCryptoError))
+                    },
+                    Message::AccountsUnlocked,
+                )
+            }
+            Message::AccountsUnlocked(Ok((store, saved))) => {
+                // 本次运行中登录的账号在前
+                for account in saved {
+                    if !self.accounts.iter().any(|a| a.uid == account.uid) {
+                        self.accounts.push(account);
+                    }
+                }
+                self.account_store = Some(store);
+                self.account_error = None;
+                self.save_accounts();
+                Task::none()
+            }
+            Message::AccountsUnlocked(Err(e)) => {
+                error!("Failed to unlock saved accounts: {}", e);
+                self.account_error = Some(e.to_string());
+                Task::none()
+            }
+            Message::AddAccount => {
+                if !self.can_switch() {
+                    return Task::none();
+                }
+                self.park();
+                request_qrcode(self.api.clone())
+            }
             _ => Task::none(),
         }
     }
 
     fn view(&self) -> Element<Message> {
-        match &self.screen {
+        let screen = match &self.screen {
             Screen::WaitingForInputCookie(c) => c.view().map(Message::Cookie),
             Screen::WaitScanQRcode(q) => q.view().map(Message::QRCode),
             Screen::Main(m) => m.view().map(Message::Main),
+        };
+        if self.accounts.is_empty() && self.account_store.is_some() && self.account_error.is_none()
+        {
+            return screen;
+        }
+        let notice = match (&self.account_error, &self.account_store) {
+            (Some(e), _) => Some(e.as_str()),
+            (None, None) => Some("Accounts are not saved, enter a passphrase to save them"),
+            (None, Some(_)) => None,
+        };
+        let mut bar = row![]
+            .push_maybe(notice.map(|n| text(n).size(14).shaping(text::Shaping::Advanced)))
+            .push_maybe(self.account_store.is_none().then(|| {
+                row![
+                    text_input("Passphrase", &self.passphrase)
+                        .secure(true)
+                        .size(14)
+                        .width(150)
+                        .padding(3)
+                        .on_input(Message::PassphraseChanged)
+                        .on_submit(Message::UnlockAccounts),
+                    button(text("Unlock").size(14)).padding(3).on_press_maybe(
+                        (!self.passphrase.is_empty()).then_some(Message::UnlockAccounts)
+                    ),
+                ]
+                .spacing(5)
+            }))
+            .push(Space::with_width(Length::Fill));
+        if !self.accounts.is_empty() {
+            let selected = self
+                .current
+                .and_then(|uid| self.accounts.iter().find(|a| a.uid == uid))
+                .cloned();
+            bar = bar.push(
+                pick_list(self.accounts.clone(), selected, |a| {
+                    Message::SwitchAccount(a.uid)
+                })
+                .placeholder("Switch account")
+                .text_size(14)
+                .padding(3),
+            );
+            bar = bar.push(
+                button(text("Add account").size(14))
+                    .padding(3)
+                    .on_press_maybe(self.can_switch().then_some(Message::AddAccount)),
+            );
         }
+        let bar = bar.spacing(5).padding(5).align_y(Alignment::Center);
+        column![bar, screen].into()
     }
     fn subscription(&self) -> Subscription<Message> {
         if let Screen::WaitScanQRcode(_) = &self.screen {
@@ -222,16 +397,129 @@ impl App {
         main_subscription()
     }
 
+    /// 查询当前页的评论、弹幕所在的视频等的标题
+    fn resolve_parents(&mut self) -> Task<Message> {
+        let Screen::Main(m) = &mut self.screen else {
+            return Task::none();
+        };
+        Task::batch(m.unresolved_parents().into_iter().map(|(parent, uri)| {
+            let api = self.api.clone();
+            Task::perform(
+                async move { meta::resolve(&api, parent, uri.as_deref()).await },
+                move |res| Message::Main(main::Message::ParentResolved(parent, res)),
+            )
+        }))
+    }
+
     fn send_to_channel(&self, m: ChannelMsg) {
         let sender = self.sender.as_ref().unwrap().clone();
         spawn(async move { sender.send(m).await });
     }
+
+    /// 登录成功，进入主界面并开始获取
+    fn boot(&mut self, api: ApiService, session: Session, aicu_state: bool) -> Task<Message> {
+        self.api = Arc::new(self.with_store(api));
+        self.pending_session = Some(session);
+        self.current = None;
+        self.screen = Screen::Main(main::Main::new(aicu_state));
+        let api = self.api.clone();
+        Task::batch([
+            self.start_fetch(),
+            Task::perform(
+                async move { api.get_account().await },
+                Message::AccountLoaded,
+            ),
+        ])
+    }
+
+    fn login_saved(&mut self, account: Account) -> Task<Message> {
+        match ApiService::from_session(account.session()) {
+            Ok(api) => self.boot(
+                api,
+                account.session(),
+                self.aicu_state.load(Ordering::SeqCst),
+            ),
+            Err(e) => {
+                error!("Saved cookie of {} is invalid: {}", account, e);
+                request_qrcode(self.api.clone())
+            }
+        }
+    }
+
+    fn start_fetch(&mut self) -> Task<Message> {
+        // 重新开始时停止上一次获取
+        if let Some(handle) = self.fetch_handle.take() {
+            handle.abort();
+        }
+        if let Screen::Main(ref m) = self.screen {
+            let (t, handle) =
+                source::fetch_task(self.api.clone(), m.sources.clone(), m.progress.clone())
+                    .abortable();
+            self.fetch_handle = Some(handle);
+            t
+        } else {
+            Task::none()
+        }
+    }
+
+    /// 删除进行中的消息不区分账号，此时不能切换
+    fn can_switch(&self) -> bool {
+        match &self.screen {
+            Screen::Main(m) => !m.is_deleting(),
+            _ => true,
+        }
+    }
+
+    /// 保留当前账号的界面，回到登录界面；未完成的获取会在切换回来时从保存的进度继续
+    fn park(&mut self) {
+        if let Some(handle) = self.fetch_handle.take() {
+            handle.abort();
+        }
+        let screen = std::mem::replace(&mut self.screen, Screen::new(self.aicu_state.clone()));
+        let api = std::mem::replace(&mut self.api, Arc::new(ApiService::default()));
+        if let (Screen::Main(m), Some(uid)) = (screen, self.current.take()) {
+            self.parked.insert(uid, (api, m));
+        }
+        self.pending_session = None;
+    }
+
+    /// 刷新后的 Cookie 会写回保存的账号
+    fn with_store(&self, api: ApiService) -> ApiService {
+        match &self.account_store {
+            Some(store) => api.with_account_store(store.clone()),
+            None => api,
+        }
+    }
+
+    fn save_accounts(&mut self) {
+        // 请求中可能刷新过 Cookie，以正在使用的为准
+        let live: Vec<_> = self
+            .current
+            .map(|uid| (uid, self.api.session()))
+            .into_iter()
+            .chain(
+                self.parked
+                    .iter()
+                    .map(|(uid, (api, _))| (*uid, api.session())),
+            )
+            .collect();
+        for (uid, session) in live {
+            if let Some(account) = self.accounts.iter_mut().find(|a| a.uid == uid) {
+                account.cookie = session.cookie;
+                account.refresh_token = session.refresh_token;
+            }
+        }
+        if let Some(store) = &self.account_store {
+            if let Err(e) = store.save(&self.accounts) {
+                error!("Failed to save accounts: {}", e);
+                self.account_error = Some(format!("Accounts not saved: {}", e));
+            }
+        }
+    }
 }
 
-fn fetch_task(
-    api: Arc<ApiService>,
-    aicu_state: bool,
-    progress: FetchProgressState,
-) -> Task<Message> {
-    notify::fetch_task(api.clone(), aicu_state, progress)
+fn request_qrcode(api: Arc<ApiService>) -> Task<Message> {
+    Task::perform(QRdata::request_qrcode(api), |a| {
+        Message::QRCode(qrcode::Message::QRcodeGot(a))
+    })
 }
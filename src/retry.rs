@@ -0,0 +1,59 @@
+//! 删除失败后的重试策略：只重试网络错误、风控等暂时性失败
+
+use crate::types::{BiliApiError, Error};
+use rand::Rng;
+use std::time::Duration;
+
+/// 重试次数与退避时间
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 包括第一次在内最多尝试的次数
+    pub max_attempts: u32,
+    /// 第一次重试前的等待时间（秒），之后每次翻倍
+    pub backoff: f32,
+    /// 等待时间上限（秒）
+    pub max_backoff: f32,
+    /// 在等待时间上随机增减的比例，避免固定节奏
+    pub jitter: f32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: 5.0,
+            max_backoff: 60.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次（从 1 开始）失败后、下一次尝试前的等待时间
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = 2f32.powi(attempt.saturating_sub(1).min(16) as i32);
+        let base = (self.backoff * exp).min(self.max_backoff);
+        let jitter = base * self.jitter.clamp(0.0, 1.0);
+        // 无穷大或 NaN 时不能随机取值
+        let secs = if jitter > 0.0 && (base + jitter).is_finite() {
+            rand::rng().random_range(base - jitter..=base + jitter)
+        } else {
+            base
+        };
+        // 超出 Duration 的范围时取最大值，不会 panic
+        Duration::try_from_secs_f32(secs.max(0.0)).unwrap_or(Duration::MAX)
+    }
+
+    /// 是否还应该再试一次
+    pub fn should_retry(&self, attempt: u32, err: &Error) -> bool {
+        attempt < self.max_attempts && is_transient(err)
+    }
+}
+
+/// 网络错误和风控可以稍后重试；已删除、无权限等其他失败重试也没有意义
+pub fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::RequestFailed(_) => true,
+        _ => matches!(err.api_error(), Some(BiliApiError::RateLimited { .. })),
+    }
+}
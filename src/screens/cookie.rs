@@ -1,6 +1,8 @@
+use crate::browser::{self, Profile};
 use crate::http::api_service::ApiService;
+use crate::types::{Error, Result};
 use iced::{
-    widget::{button, center, column, row, text_input, toggler, Space},
+    widget::{button, center, column, pick_list, row, text, text_input, toggler, Space},
     Element, Length, Task,
 };
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -11,6 +13,10 @@ use tracing::error;
 pub struct Cookie {
     cookie: String,
     aicu_state: Arc<AtomicBool>,
+    error: Option<String>,
+    /// 本机找到的浏览器配置
+    profiles: Vec<Profile>,
+    profile: Option<Profile>,
 }
 
 #[derive(Debug, Clone)]
@@ -19,41 +25,74 @@ pub enum Message {
     CookieInputChanged(String),
     AicuToggled(bool),
     EntertoQRcodeScan,
+    /// 从 cookies.txt 或导出的 JSON 读取
+    LoadCookieFile,
+    CookieFileLoaded(Result<Option<String>>),
+    ProfileSelected(Profile),
+    ImportFromBrowser,
+    BrowserCookieRead(Result<String>),
 }
 
 pub enum Action {
     Run(Task<Message>),
-    Boot { api: ApiService, aicu_state: bool },
+    Boot {
+        api: ApiService,
+        cookie: String,
+        aicu_state: bool,
+    },
     EnterQRCode,
     None,
 }
 
 impl Cookie {
     pub fn new(aicu_state: Arc<AtomicBool>) -> Self {
+        let profiles = browser::profiles();
         Cookie {
             cookie: String::new(),
             aicu_state,
+            error: None,
+            profile: profiles.first().cloned(),
+            profiles,
         }
     }
     pub fn view(&self) -> Element<Message> {
         let cookie = &self.cookie;
         center(
-            column![
+            column![row![
+                text_input("Input cookie here", cookie)
+                    .on_input(Message::CookieInputChanged)
+                    .on_submit(Message::CookieSubmited(cookie.clone())),
+                button("enter").on_press(Message::CookieSubmited(cookie.clone())),
+                button("Load file").on_press(Message::LoadCookieFile),
+            ]
+            .spacing(5)]
+            .push_maybe((!self.profiles.is_empty()).then(|| {
                 row![
-                    text_input("Input cookie here", cookie)
-                        .on_input(Message::CookieInputChanged)
-                        .on_submit(Message::CookieSubmited(cookie.clone())),
-                    button("enter").on_press(Message::CookieSubmited(cookie.clone())),
+                    pick_list(
+                        self.profiles.as_slice(),
+                        self.profile.clone(),
+                        Message::ProfileSelected
+                    ),
+                    button("Import from browser").on_press_maybe(
+                        self.profile.is_some().then_some(Message::ImportFromBrowser)
+                    ),
                 ]
-                .spacing(5),
+                .spacing(5)
+            }))
+            .push(
                 toggler(self.aicu_state.load(Ordering::SeqCst))
                     .on_toggle(Message::AicuToggled)
                     .label("Also fetch comments from aicu.cc"),
-                row![
-                    Space::with_width(Length::Fill),
-                    button("Change to scan QR code").on_press(Message::EntertoQRcodeScan)
-                ]
-            ]
+            )
+            .push(row![
+                Space::with_width(Length::Fill),
+                button("Change to scan QR code").on_press(Message::EntertoQRcodeScan)
+            ])
+            .push_maybe(
+                self.error
+                    .as_ref()
+                    .map(|e| text(e).shaping(text::Shaping::Advanced)),
+            )
             .spacing(5),
         )
         .padding(20)
@@ -66,16 +105,67 @@ impl Cookie {
                 let api = ApiService::new(s);
                 match api {
                     Ok(api) => {
+                        self.error = None;
                         return Action::Boot {
+                            cookie: api.session().cookie,
                             api,
                             aicu_state: self.aicu_state.load(Ordering::SeqCst),
                         };
                     }
                     Err(e) => {
-                        error!("{}", e)
+                        error!("{}", e);
+                        self.error = Some(e.to_string());
                     }
                 }
             }
+            Message::LoadCookieFile => {
+                return Action::Run(Task::perform(
+                    async {
+                        let Some(file) = rfd::AsyncFileDialog::new()
+                            .add_filter("Cookie", &["txt", "json"])
+                            .pick_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        Ok(Some(std::fs::read_to_string(file.path())?))
+                    },
+                    Message::CookieFileLoaded,
+                ));
+            }
+            Message::ProfileSelected(profile) => {
+                self.profile = Some(profile);
+            }
+            Message::ImportFromBrowser => {
+                if let Some(profile) = self.profile.clone() {
+                    return Action::Run(Task::perform(
+                        async move {
+                            // 复制、读取数据库是阻塞操作，不占用界面的运行时线程
+                            tokio::task::spawn_blocking(move || browser::read_cookie(&profile))
+                                .await
+                                .unwrap_or_else(|e| {
+                                    Err(Error::BrowserCookieError(Arc::new(e.to_string())))
+                                })
+                        },
+                        Message::BrowserCookieRead,
+                    ));
+                }
+            }
+            Message::BrowserCookieRead(res) => match res {
+                Ok(s) => return self.update(Message::CookieSubmited(s)),
+                Err(e) => {
+                    error!("{}", e);
+                    self.error = Some(e.to_string());
+                }
+            },
+            Message::CookieFileLoaded(res) => match res {
+                Ok(Some(s)) => return self.update(Message::CookieSubmited(s)),
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to read cookie file: {}", e);
+                    self.error = Some(format!("Failed to read cookie file: {}", e));
+                }
+            },
             Message::CookieInputChanged(s) => {
                 self.cookie = s;
             }
@@ -1,59 +1,85 @@
-pub mod comment_viewer;
-pub mod danmu_viewer;
-pub mod notify_viewer;
+pub mod filter_bar;
+pub mod item_list;
+pub mod item_viewer;
 
+use crate::archive;
+use crate::dry_run;
 use crate::http::comment::Comment;
 use crate::http::danmu::Danmu;
+use crate::http::meta::ParentInfo;
 use crate::http::notify::Notify;
-use crate::screens::main::danmu_viewer::DanmuViewer;
-use crate::screens::main::notify_viewer::NotifyViewer;
+use crate::http::source::{Registry, Status};
+use crate::retry::RetryPolicy;
+use crate::storage::ParentCache;
+use crate::types::AggregatedData;
 use crate::types::ChannelMsg;
+use crate::types::Deletable;
 use crate::types::FetchProgressState;
+use crate::types::Parent;
 use crate::types::Result;
-use comment_viewer::CommentViewer;
+use crate::Type;
 use iced::widget::center;
 use iced::widget::column;
 use iced::Alignment;
 use iced::Task;
 use iced::{
-    widget::{button, container, pane_grid, row, text},
-    Element,
+    widget::{button, container, pane_grid, pick_list, row, text, toggler, Space},
+    Element, Length,
 };
-use std::collections::HashMap;
+use item_viewer::{AnyViewerMsg, ItemViewer, Viewer};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// 可选的删除最多尝试次数
+const MAX_ATTEMPTS: [u32; 5] = [1, 2, 3, 5, 10];
+/// 可选的第一次重试前的等待时间（秒）
+const BACKOFF: [f32; 5] = [1.0, 5.0, 15.0, 30.0, 60.0];
+/// 可选的两次删除请求之间的最短间隔（秒）
+const DELETE_INTERVAL: [f32; 6] = [0.5, 1.0, 2.0, 3.0, 5.0, 10.0];
 
 pub struct Main {
-    panes: pane_grid::State<Pane>,
+    panes: pane_grid::State<Type>,
     focus: Option<pane_grid::Pane>,
-    cv: CommentViewer,
-    nv: NotifyViewer,
-    dv: DanmuViewer,
-    /// Retrying the fetch requires
-    pub aicu_state: bool,
+    /// 各类项的查看器，按注册顺序从左到右排列
+    viewers: Vec<Box<dyn Viewer>>,
+    /// 获取时使用的来源及是否启用
+    pub sources: Registry,
+    /// 各来源的获取进度
+    source_status: HashMap<&'static str, Status>,
     error: Option<String>,
     pub progress: FetchProgressState,
     could_continue: bool,
+    /// 导入导出结果
+    status: Option<String>,
+    /// 已从文件导入，忽略之后获取到的结果
+    imported: bool,
+    /// 演练模式，只生成报告不删除
+    dry_run: bool,
+    /// 删除失败时的重试次数与等待时间
+    pub retry: RetryPolicy,
+    /// 两次删除请求之间的最短间隔（秒）
+    pub delete_interval: f32,
+    /// 已解析的所在对象的标题与链接
+    parents: HashMap<Parent, ParentInfo>,
+    /// 本次运行中已请求过的对象，失败的在下次显示时重试
+    requested: HashSet<Parent>,
+    /// 正在解析的数量，全部完成后写入缓存
+    resolving: usize,
+    parent_cache: Option<ParentCache>,
 }
 impl fmt::Debug for Main {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Main")
             .field("panes", &"<HIDDEN>")
             .field("focus", &self.focus)
-            .field("cv", &self.cv)
-            .field("nv", &self.nv)
-            .field("dv", &self.dv)
+            .field("viewers", &self.viewers)
             .finish()
     }
 }
 
-enum Pane {
-    CommentViewer,
-    DmViewer,
-    NotifyViewer,
-}
-
 #[derive(Debug, Clone)]
 pub enum Message {
     PaneDragged(pane_grid::DragEvent),
@@ -62,76 +88,91 @@ pub enum Message {
     PaneRestore,
     PaneClicked(pane_grid::Pane),
 
-    CommentMsg(comment_viewer::CvMsg),
-    NotifyMsg(notify_viewer::NvMsg),
-    DanmuMsg(danmu_viewer::DvMsg),
-
-    Fetched(
-        Result<(
-            Option<
-                Arc<(
-                    HashMap<u64, Notify>,
-                    HashMap<u64, Comment>,
-                    HashMap<u64, Danmu>,
-                )>,
-            >,
-            Option<FetchProgressState>,
-        )>,
-    ),
+    /// 交给同一类型的查看器处理
+    Viewer(AnyViewerMsg),
+
+    Fetched(Result<(Option<Arc<AggregatedData>>, Option<FetchProgressState>)>),
     RetryFetch,
-    RefreshUI(()),
+    SourceProgress(&'static str, Status),
+    SourceToggled(&'static str, bool),
+
+    Export,
+    Exported(Result<Option<PathBuf>>),
+    Import,
+    Imported(Result<Option<(PathBuf, AggregatedData)>>),
+
+    DryRunToggled(bool),
+    DryRunDone(Result<dry_run::Summary>),
+    MaxAttemptsChanged(u32),
+    DeleteIntervalChanged(f32),
+    BackoffChanged(f32),
+
+    ParentResolved(Parent, Result<Option<ParentInfo>>),
 }
 
 pub enum Action {
     Run(Task<Message>),
 
-    DeleteComment {
-        comments: Arc<Mutex<HashMap<u64, Comment>>>,
-        sleep_seconds: f32,
-    },
-
-    DeleteNotify {
-        notify: Arc<Mutex<HashMap<u64, Notify>>>,
-        sleep_seconds: f32,
-    },
-
-    DeleteDanmu {
-        danmu: Arc<Mutex<HashMap<u64, Danmu>>>,
-        sleep_seconds: f32,
+    /// 删除一类项中选中的项
+    Delete {
+        items: Box<dyn Deletable>,
     },
 
     RetryFetch,
+    /// 数据已从文件导入，不再需要获取
+    AbortFetch,
 
     SendtoChannel(ChannelMsg),
     None,
 }
 impl Main {
     pub fn new(aicu_state: bool) -> Self {
-        let pane_comment = pane_grid::Configuration::Pane(Pane::CommentViewer);
-        let pane_dm = pane_grid::Configuration::Pane(Pane::DmViewer);
-        let pane_notify = pane_grid::Configuration::Pane(Pane::NotifyViewer);
-        let pane_left_side = pane_grid::Configuration::Split {
-            axis: pane_grid::Axis::Vertical,
-            ratio: 0.5,
-            a: Box::new(pane_comment),
-            b: Box::new(pane_dm),
-        };
-        let cfg = pane_grid::Configuration::Split {
-            axis: pane_grid::Axis::Vertical,
-            ratio: 2. / 3.,
-            a: Box::new(pane_left_side),
-            b: Box::new(pane_notify),
-        };
+        let viewers: Vec<Box<dyn Viewer>> = vec![
+            Box::new(ItemViewer::<Comment>::new()),
+            Box::new(ItemViewer::<Danmu>::new()),
+            Box::new(ItemViewer::<Notify>::new()),
+        ];
+        // 各查看器等宽
+        let cfg = viewers
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, v)| (i, pane_grid::Configuration::Pane(v.tp())))
+            .reduce(|(_, b), (i, a)| {
+                let split = pane_grid::Configuration::Split {
+                    axis: pane_grid::Axis::Vertical,
+                    ratio: 1. / (i + 1) as f32,
+                    a: Box::new(a),
+                    b: Box::new(b),
+                };
+                (i, split)
+            })
+            .expect("at least one viewer")
+            .1;
+        let parent_cache = ParentCache::open()
+            .inspect_err(|e| warn!("Parent cache is unavailable: {}", e))
+            .ok();
         Main {
             panes: pane_grid::State::with_configuration(cfg),
             focus: None,
-            cv: CommentViewer::new(),
-            nv: NotifyViewer::new(),
-            dv: DanmuViewer::new(),
-            aicu_state,
+            viewers,
+            sources: Registry::default().with_aicu(aicu_state),
+            source_status: HashMap::new(),
             error: None,
             progress: FetchProgressState::default(),
             could_continue: false,
+            status: None,
+            imported: false,
+            dry_run: false,
+            retry: RetryPolicy::default(),
+            delete_interval: 3.0,
+            parents: parent_cache
+                .as_ref()
+                .map(ParentCache::load)
+                .unwrap_or_default(),
+            requested: HashSet::new(),
+            resolving: 0,
+            parent_cache,
         }
     }
     pub fn update(&mut self, message: Message) -> Action {
@@ -151,21 +192,23 @@ impl Main {
                 self.focus = Some(pane);
             }
 
-            Message::CommentMsg(m) => return self.cv.update(m),
-            Message::NotifyMsg(m) => return self.nv.update(m),
-            Message::DanmuMsg(m) => return self.dv.update(m),
+            Message::Viewer(m) => {
+                let action = match self.viewers.iter_mut().find(|v| v.tp() == m.tp()) {
+                    Some(viewer) => viewer.update(m),
+                    None => Action::None,
+                };
+                return self.dry_run_or(action);
+            }
 
+            Message::Fetched(_) if self.imported => {}
             Message::Fetched(res) => {
                 if let Ok((arc_tuple, progress)) = res {
                     if let Some(p) = progress {
                         self.progress = p;
                         self.could_continue = true;
                     } else if let Some(arc_tuple) = arc_tuple {
-                        let (notify, comments, danmu) = Arc::as_ref(&arc_tuple);
                         self.could_continue = false;
-                        self.nv.notify = Some(Arc::new(Mutex::new(notify.clone())));
-                        self.cv.comments = Some(Arc::new(Mutex::new(comments.clone())));
-                        self.dv.danmu = Some(Arc::new(Mutex::new(danmu.clone())));
+                        self.load(Arc::unwrap_or_clone(arc_tuple));
                     }
                 } else {
                     self.error = Some(format!("{:?}", res.err()));
@@ -173,13 +216,184 @@ impl Main {
             }
             Message::RetryFetch => {
                 self.error = None;
+                self.could_continue = false;
                 return Action::RetryFetch;
             }
 
-            Message::RefreshUI(_) => {}
+            Message::SourceProgress(id, status) => {
+                self.source_status.insert(id, status);
+            }
+            Message::SourceToggled(id, enabled) => {
+                self.sources.set_enabled(id, enabled);
+                self.source_status.remove(id);
+                // 正在获取时重新开始，已获取的页不会重复获取
+                if self.is_fetching() {
+                    return Action::RetryFetch;
+                }
+            }
+
+            Message::Export => {
+                let mut data = AggregatedData::default();
+                for viewer in &self.viewers {
+                    viewer.export(&mut data);
+                }
+                let codecs = self.codecs();
+                return Action::Run(Task::perform(
+                    async move {
+                        let Some(file) = rfd::AsyncFileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .add_filter("CSV", &["csv"])
+                            .set_file_name("bilibili-comment-cleaning.json")
+                            .save_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        let path = file.path().to_path_buf();
+                        archive::export(&path, &data, &codecs)?;
+                        Ok(Some(path))
+                    },
+                    Message::Exported,
+                ));
+            }
+            Message::Exported(res) => match res {
+                Ok(Some(path)) => {
+                    self.status = Some(format!("Exported to {}", path.display()));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to export: {}", e);
+                    self.status = Some(format!("Failed to export: {}", e));
+                }
+            },
+            Message::Import => {
+                let codecs = self.codecs();
+                return Action::Run(Task::perform(
+                    async move {
+                        let Some(file) = rfd::AsyncFileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .add_filter("CSV", &["csv"])
+                            .pick_file()
+                            .await
+                        else {
+                            return Ok(None);
+                        };
+                        let path = file.path().to_path_buf();
+                        let data = archive::import(&path, &codecs)?;
+                        Ok(Some((path, data)))
+                    },
+                    Message::Imported,
+                ));
+            }
+            Message::Imported(res) => match res {
+                Ok(Some((path, data))) => {
+                    self.imported = true;
+                    self.could_continue = false;
+                    self.error = None;
+                    self.load(data);
+                    self.status = Some(format!("Imported from {}", path.display()));
+                    return Action::AbortFetch;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to import: {}", e);
+                    self.status = Some(format!("Failed to import: {}", e));
+                }
+            },
+
+            Message::DryRunToggled(b) => {
+                self.dry_run = b;
+            }
+            Message::MaxAttemptsChanged(n) => {
+                self.retry.max_attempts = n;
+            }
+            Message::DeleteIntervalChanged(secs) => {
+                self.delete_interval = secs;
+            }
+            Message::BackoffChanged(secs) => {
+                self.retry.backoff = secs;
+            }
+            Message::DryRunDone(res) => match res {
+                Ok(summary) => {
+                    info!("{}", summary);
+                    self.status = Some(summary.to_string());
+                }
+                Err(e) => {
+                    error!("Dry run failed: {}", e);
+                    self.status = Some(format!("Dry run failed: {}", e));
+                }
+            },
+
+            Message::ParentResolved(parent, res) => {
+                self.resolving -= 1;
+                match res {
+                    Ok(Some(info)) => {
+                        self.parents.insert(parent, info);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("Failed to resolve {}: {}", parent, e);
+                        // 下次显示时重新请求
+                        self.requested.remove(&parent);
+                    }
+                }
+                if self.resolving == 0 {
+                    if let Some(cache) = &self.parent_cache {
+                        cache.save(&self.parents);
+                    }
+                }
+            }
         }
         Action::None
     }
+
+    /// 评论、弹幕当前页中尚未解析的所在对象，返回后视为正在解析
+    pub fn unresolved_parents(&mut self) -> Vec<(Parent, Option<String>)> {
+        let mut parents: Vec<_> = self.viewers.iter().flat_map(|v| v.page_parents()).collect();
+        parents.retain(|(p, _)| !self.parents.contains_key(p) && self.requested.insert(*p));
+        self.resolving += parents.len();
+        parents
+    }
+
+    /// 正在删除，不能切换账号或导入
+    pub fn is_deleting(&self) -> bool {
+        self.viewers.iter().any(|v| v.is_deleting())
+    }
+
+    /// 已获取或导入数据，切换回来时无需重新获取
+    pub fn has_data(&self) -> bool {
+        self.viewers.iter().any(|v| v.has_items())
+    }
+
+    /// 获取与导入的数据由各查看器取出自己的一类
+    fn load(&mut self, mut data: AggregatedData) {
+        for viewer in &mut self.viewers {
+            viewer.load(&mut data);
+        }
+    }
+
+    /// 导出导入注册的查看器对应的类型
+    fn codecs(&self) -> Vec<archive::Codec> {
+        self.viewers.iter().map(|v| v.codec()).collect()
+    }
+
+    fn is_fetching(&self) -> bool {
+        !self.has_data() && !self.could_continue && self.error.is_none()
+    }
+
+    /// 演练模式下拦截删除，改为生成报告
+    fn dry_run_or(&mut self, action: Action) -> Action {
+        if !self.dry_run {
+            return action;
+        }
+        let Action::Delete { items } = action else {
+            return action;
+        };
+        if let Some(viewer) = self.viewers.iter_mut().find(|v| v.tp() == items.tp()) {
+            viewer.stop_deleting();
+        }
+        Action::Run(Task::perform(items.dry_run(), Message::DryRunDone))
+    }
     pub fn view(&self) -> Element<'_, Message> {
         if let Some(ref e) = self.error {
             return center(
@@ -198,6 +412,7 @@ impl Main {
             return center(
                 column![
                     text("Some anticipated errors occurred, but they were recoverable."),
+                    self.view_sources(),
                     button("Continue").on_press(Message::RetryFetch),
                 ]
                 .align_x(Alignment::Center)
@@ -208,12 +423,7 @@ impl Main {
         let focus = self.focus;
         let pane_grid = pane_grid(&self.panes, |pane, state, is_maximized| {
             let is_focused = focus == Some(pane);
-            let title = match state {
-                Pane::CommentViewer => "comment",
-                Pane::DmViewer => "danmu",
-                Pane::NotifyViewer => "notify",
-            };
-            let titlebar = pane_grid::TitleBar::new(text(title))
+            let titlebar = pane_grid::TitleBar::new(text(state.id()))
                 .controls(pane_grid::Controls::new(view_controls(pane, is_maximized)))
                 .padding(3)
                 .style(if is_focused {
@@ -222,10 +432,10 @@ impl Main {
                     style::title_bar_active
                 });
 
-            pane_grid::Content::new(match state {
-                Pane::CommentViewer => self.cv.view().map(Message::CommentMsg),
-                Pane::DmViewer => self.dv.view().map(Message::DanmuMsg),
-                Pane::NotifyViewer => self.nv.view().map(Message::NotifyMsg),
+            let viewer = self.viewers.iter().find(|v| v.tp() == *state);
+            pane_grid::Content::new(match viewer {
+                Some(viewer) => viewer.view(&self.parents),
+                None => Space::new(Length::Fill, Length::Fill).into(),
             })
             .title_bar(titlebar)
             .style(if is_focused {
@@ -239,7 +449,80 @@ impl Main {
         .on_click(Message::PaneClicked)
         .spacing(5);
 
-        container(pane_grid).padding(5).into()
+        let toolbar = row![
+            text(self.status.as_deref().unwrap_or_default()).shaping(text::Shaping::Advanced),
+            Space::with_width(Length::Fill),
+            text("Interval (s)").size(14),
+            pick_list(
+                DELETE_INTERVAL,
+                Some(self.delete_interval),
+                Message::DeleteIntervalChanged
+            )
+            .text_size(14)
+            .padding(3),
+            text("Attempts").size(14),
+            pick_list(
+                MAX_ATTEMPTS,
+                Some(self.retry.max_attempts),
+                Message::MaxAttemptsChanged
+            )
+            .text_size(14)
+            .padding(3),
+            text("Backoff (s)").size(14),
+            pick_list(BACKOFF, Some(self.retry.backoff), Message::BackoffChanged)
+                .text_size(14)
+                .padding(3),
+            toggler(self.dry_run)
+                .on_toggle(Message::DryRunToggled)
+                .label("Dry run")
+                .text_size(14),
+            button(text("Import").size(14))
+                .padding(3)
+                .on_press_maybe((!self.is_deleting()).then_some(Message::Import)),
+            button(text("Export").size(14))
+                .padding(3)
+                .on_press_maybe(self.has_data().then_some(Message::Export)),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center);
+
+        container(
+            column![toolbar]
+                .push_maybe((!self.has_data()).then(|| self.view_sources()))
+                .push(pane_grid)
+                .spacing(5),
+        )
+        .padding(5)
+        .into()
+    }
+
+    /// 各来源的开关与进度
+    fn view_sources(&self) -> Element<'_, Message> {
+        column(self.sources.iter().map(|(source, enabled)| {
+            let id = source.id();
+            let status = if enabled {
+                self.source_status
+                    .get(id)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string()
+            } else {
+                "disabled".to_string()
+            };
+            row![
+                toggler(enabled)
+                    .on_toggle(move |b| Message::SourceToggled(id, b))
+                    .label(source.name())
+                    .text_size(14)
+                    .width(Length::Fixed(200.0)),
+                text(status).size(14),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .into()
+        }))
+        .spacing(3)
+        .into()
     }
 }
 
@@ -0,0 +1,74 @@
+use crate::filter::{Filter, FilterAction, FilterMode};
+use iced::widget::{button, pick_list, row, text, text_input};
+use iced::{Alignment, Element, Length};
+
+/// 各个 viewer 共用的筛选栏
+#[derive(Debug, Default)]
+pub struct FilterBar {
+    mode: FilterMode,
+    input: String,
+    /// 正则或数字无效
+    error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum FbMsg {
+    ModeSelected(FilterMode),
+    InputChanged(String),
+    Apply(FilterAction),
+}
+
+impl FilterBar {
+    pub fn view(&self, is_deleting: bool) -> Element<'_, FbMsg> {
+        let can_apply = !is_deleting && !self.input.is_empty();
+        let apply = |label, action| {
+            button(text(label).size(14))
+                .padding(3)
+                .on_press_maybe(can_apply.then_some(FbMsg::Apply(action)))
+        };
+        let placeholder = match self.mode {
+            FilterMode::Contains => "keyword",
+            FilterMode::Regex => "regex",
+            FilterMode::MinLength | FilterMode::MaxLength => "length",
+            FilterMode::Before | FilterMode::After => "2023-01-01 12:00",
+            FilterMode::Between => "2023-01-01..2023-06-30",
+        };
+
+        let mut bar = row![
+            pick_list(FilterMode::ALL, Some(self.mode), FbMsg::ModeSelected).text_size(14),
+            text_input(placeholder, &self.input)
+                .size(14)
+                .on_input_maybe((!is_deleting).then_some(FbMsg::InputChanged))
+                .on_submit_maybe(can_apply.then_some(FbMsg::Apply(FilterAction::Only)))
+                .width(Length::Fill),
+            apply("select", FilterAction::Select),
+            apply("deselect", FilterAction::Deselect),
+            apply("only", FilterAction::Only),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center);
+        if let Some(e) = &self.error {
+            bar = bar.push(text(e).size(12).shaping(text::Shaping::Advanced));
+        }
+        bar.into()
+    }
+
+    /// 点击应用且输入有效时返回筛选条件
+    pub fn update(&mut self, msg: FbMsg) -> Option<(Filter, FilterAction)> {
+        match msg {
+            FbMsg::ModeSelected(mode) => {
+                self.mode = mode;
+                self.error = None;
+            }
+            FbMsg::InputChanged(input) => {
+                self.input = input;
+                self.error = None;
+            }
+            FbMsg::Apply(action) => match Filter::new(self.mode, &self.input) {
+                Ok(filter) => return Some((filter, action)),
+                Err(e) => self.error = Some(e.to_string()),
+            },
+        }
+        None
+    }
+}
@@ -0,0 +1,318 @@
+use crate::types::{Item, Parent};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// 每页显示的行数
+pub const PAGE_SIZE: usize = 100;
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    Time,
+    Id,
+    Type,
+    /// 字数
+    Length,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 4] = [SortBy::Time, SortBy::Id, SortBy::Type, SortBy::Length];
+}
+
+impl Display for SortBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortBy::Time => write!(f, "time"),
+            SortBy::Id => write!(f, "id"),
+            SortBy::Type => write!(f, "type"),
+            SortBy::Length => write!(f, "length"),
+        }
+    }
+}
+
+/// 查看器持有的列表：按键索引，勾选或删除一项不需要遍历或复制整个列表
+#[derive(Debug)]
+pub struct ItemList<T: Item> {
+    /// 按当前的排序排列；已删除的位置留空，删除结束后再整理，删除过程中其余各项不会换页
+    slots: Vec<Option<(T::Key, T)>>,
+    /// 键在 slots 中的位置
+    index: HashMap<T::Key, usize>,
+    /// 符合搜索的项在 slots 中的位置
+    shown: Vec<usize>,
+    /// 符合搜索的各组的项数
+    groups: HashMap<Option<Parent>, usize>,
+    selected: usize,
+    /// 选中且符合搜索的项数
+    shown_selected: usize,
+    sort: SortBy,
+    descending: bool,
+    grouped: bool,
+    /// 小写的搜索词
+    query: String,
+}
+
+impl<T: Item> ItemList<T> {
+    /// 默认从新到旧排列
+    pub fn new(items: HashMap<T::Key, T>) -> Self {
+        let mut list = Self {
+            slots: items.into_iter().map(Some).collect(),
+            index: HashMap::new(),
+            shown: Vec::new(),
+            groups: HashMap::new(),
+            selected: 0,
+            shown_selected: 0,
+            sort: SortBy::Time,
+            descending: true,
+            grouped: false,
+            query: String::new(),
+        };
+        list.resort();
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// 选中的项数
+    pub fn selected_count(&self) -> usize {
+        self.selected
+    }
+
+    /// 选中但不符合搜索的项数，这些项不会被删除
+    pub fn hidden_selected_count(&self) -> usize {
+        self.selected - self.shown_selected
+    }
+
+    /// 符合搜索的项数
+    pub fn shown_count(&self) -> usize {
+        self.groups.values().sum()
+    }
+
+    /// 符合搜索的该组的项数
+    pub fn group_len(&self, group: &Option<Parent>) -> usize {
+        self.groups.get(group).copied().unwrap_or_default()
+    }
+
+    pub fn sort(&self) -> SortBy {
+        self.sort
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.grouped
+    }
+
+    pub fn get(&self, key: &T::Key) -> Option<&T> {
+        let (_, item) = self.slots[*self.index.get(key)?].as_ref()?;
+        Some(item)
+    }
+
+    pub fn set_sort(&mut self, sort: SortBy, descending: bool) {
+        self.sort = sort;
+        self.descending = descending;
+        self.resort();
+    }
+
+    /// 按所在的视频、动态等分组，组内仍按当前的排序排列
+    pub fn set_grouped(&mut self, grouped: bool) {
+        self.grouped = grouped;
+        self.resort();
+    }
+
+    /// 只显示内容包含 `query` 的项，不区分大小写
+    pub fn search(&mut self, query: &str) {
+        self.query = query.to_lowercase();
+        self.refresh();
+    }
+
+    fn matches(&self, item: &T) -> bool {
+        self.query.is_empty() || item.content().to_lowercase().contains(&self.query)
+    }
+
+    fn compare(&self, (ka, a): &(T::Key, T), (kb, b): &(T::Key, T)) -> Ordering {
+        let order = match self.sort {
+            SortBy::Time => a.time().cmp(&b.time()),
+            SortBy::Id => ka.cmp(kb),
+            SortBy::Type => a.kind().cmp(b.kind()),
+            SortBy::Length => a
+                .content()
+                .chars()
+                .count()
+                .cmp(&b.content().chars().count()),
+        };
+        let order = if self.descending {
+            order.reverse()
+        } else {
+            order
+        };
+        let order = order.then_with(|| ka.cmp(kb));
+        if self.grouped {
+            a.parent().cmp(&b.parent()).then(order)
+        } else {
+            order
+        }
+    }
+
+    fn resort(&mut self) {
+        let mut slots: Vec<_> = std::mem::take(&mut self.slots)
+            .into_iter()
+            .flatten()
+            .collect();
+        slots.sort_unstable_by(|a, b| self.compare(a, b));
+        self.slots = slots.into_iter().map(Some).collect();
+        self.refresh();
+    }
+
+    /// 重建索引与搜索结果
+    fn refresh(&mut self) {
+        self.index.clear();
+        self.shown.clear();
+        self.groups.clear();
+        self.selected = 0;
+        self.shown_selected = 0;
+        for (i, slot) in self.slots.iter().enumerate() {
+            let Some((key, item)) = slot else {
+                continue;
+            };
+            self.index.insert(*key, i);
+            if item.is_selected() {
+                self.selected += 1;
+            }
+            if self.matches(item) {
+                self.shown.push(i);
+                *self.groups.entry(item.parent()).or_default() += 1;
+                if item.is_selected() {
+                    self.shown_selected += 1;
+                }
+            }
+        }
+    }
+
+    pub fn set_selected(&mut self, key: &T::Key, selected: bool) {
+        let Some(&i) = self.index.get(key) else {
+            return;
+        };
+        let Some((_, item)) = &self.slots[i] else {
+            return;
+        };
+        if item.is_selected() == selected {
+            return;
+        }
+        let shown = self.matches(item);
+        if let Some((_, item)) = &mut self.slots[i] {
+            item.set_selected(selected);
+        }
+        if selected {
+            self.selected += 1;
+            self.shown_selected += usize::from(shown);
+        } else {
+            self.selected -= 1;
+            self.shown_selected -= usize::from(shown);
+        }
+    }
+
+    /// 对每一项调用 `f`，返回 Some 时设置是否选中
+    pub fn select_where(&mut self, mut f: impl FnMut(&T::Key, &T) -> Option<bool>) {
+        for (key, item) in self.slots.iter_mut().flatten() {
+            if let Some(b) = f(key, item) {
+                item.set_selected(b);
+            }
+        }
+        self.count_selected();
+    }
+
+    /// 只对符合搜索的项调用 `f`
+    pub fn select_shown(&mut self, mut f: impl FnMut(&T) -> Option<bool>) {
+        for &i in &self.shown {
+            if let Some((_, item)) = &mut self.slots[i] {
+                if let Some(b) = f(item) {
+                    item.set_selected(b);
+                }
+            }
+        }
+        self.count_selected();
+    }
+
+    fn count_selected(&mut self) {
+        self.selected = self.iter().filter(|(_, item)| item.is_selected()).count();
+        self.shown_selected = self
+            .shown
+            .iter()
+            .filter_map(|&i| self.slots[i].as_ref())
+            .filter(|(_, item)| item.is_selected())
+            .count();
+    }
+
+    pub fn remove(&mut self, key: &T::Key) -> Option<T> {
+        let i = self.index.remove(key)?;
+        let (_, item) = self.slots[i].take()?;
+        let shown = self.matches(&item);
+        if item.is_selected() {
+            self.selected -= 1;
+            self.shown_selected -= usize::from(shown);
+        }
+        if shown {
+            let group = item.parent();
+            if let Some(n) = self.groups.get_mut(&group) {
+                *n -= 1;
+                if *n == 0 {
+                    self.groups.remove(&group);
+                }
+            }
+        }
+        Some(item)
+    }
+
+    /// 去掉已删除的项留下的空位
+    pub fn compact(&mut self) {
+        if self.slots.len() != self.index.len() {
+            self.slots.retain(Option::is_some);
+            self.refresh();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T::Key, &T)> {
+        self.slots.iter().flatten().map(|(key, item)| (key, item))
+    }
+
+    /// 页数，至少为 1
+    pub fn pages(&self) -> usize {
+        self.shown.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    /// 第 `page` 页（从 0 开始）符合搜索的项，已删除的项不显示
+    pub fn page(&self, page: usize) -> impl Iterator<Item = (&T::Key, &T)> {
+        let start = (page * PAGE_SIZE).min(self.shown.len());
+        let end = (start + PAGE_SIZE).min(self.shown.len());
+        self.shown[start..end]
+            .iter()
+            .filter_map(|&i| self.slots[i].as_ref())
+            .map(|(key, item)| (key, item))
+    }
+
+    /// 选中且符合搜索的项，用于删除；被搜索隐藏的项不删除
+    pub fn selected(&self) -> Vec<(T::Key, T)> {
+        self.shown
+            .iter()
+            .filter_map(|&i| self.slots[i].as_ref())
+            .filter(|(_, item)| item.is_selected())
+            .map(|(key, item)| (*key, item.clone()))
+            .collect()
+    }
+
+    pub fn to_map(&self) -> HashMap<T::Key, T> {
+        self.iter()
+            .map(|(key, item)| (*key, item.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,542 @@
+use crate::archive::{Archived, Codec};
+use crate::filter::format_time;
+use crate::http::meta::{self, ParentInfo};
+use crate::main::{self, Action};
+use crate::screens::main::filter_bar::{FbMsg, FilterBar};
+use crate::screens::main::item_list::{ItemList, SortBy};
+use crate::types::{AggregatedData, ChannelMsg, Item, Parent};
+use crate::Type;
+use iced::widget::{
+    button, center, checkbox, column, pick_list, row, scrollable, text, text_input, toggler,
+    tooltip, Column, Row, Space,
+};
+use iced::{Alignment, Element, Length, Task};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use tracing::error;
+
+/// 评论、弹幕、通知共用的查看器
+#[derive(Debug)]
+pub struct ItemViewer<T: Item> {
+    pub items: Option<ItemList<T>>,
+    /// 当前页，从 0 开始
+    pub page: usize,
+    /// 是否正在删除
+    pub is_deleting: bool,
+    /// 是否正在获取
+    /// 默认为true，在获取到数据后设置为false
+    pub is_fetching: bool,
+    /// select all | deselect all state
+    pub select_state: bool,
+    /// 按内容筛选
+    pub filter: FilterBar,
+    /// 搜索框的内容
+    pub search: String,
+    /// 删除失败的项及原因，可手动重试
+    pub failed: HashMap<T::Key, String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ViewerMsg<T: Item> {
+    Filter(FbMsg),
+    ChangeRemoveState(T::Key, bool),
+    SelectAll,
+    DeselectAll,
+    /// 选中或取消选中一组中符合搜索的项
+    SelectGroup(Option<Parent>, bool),
+    Search(String),
+    SortChanged(SortBy),
+    OrderToggled,
+    GroupToggled(bool),
+    PageChanged(usize),
+    /// 在浏览器中打开所在的视频、动态等
+    Open(String),
+    Delete,
+    StopDelete,
+    Deleted {
+        key: T::Key,
+    },
+    DeleteFailed {
+        key: T::Key,
+        error: String,
+    },
+    RetryFailed,
+    AllDeleted,
+}
+/// 不区分具体类型的查看器消息，主界面按 [`Type`] 交给对应的查看器
+#[derive(Clone)]
+pub struct AnyViewerMsg {
+    tp: Type,
+    delete_failed: bool,
+    msg: Arc<dyn ErasedMsg>,
+}
+
+trait ErasedMsg: Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Debug + Send + Sync + 'static> ErasedMsg for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl AnyViewerMsg {
+    pub fn tp(&self) -> Type {
+        self.tp
+    }
+
+    /// 是否为删除失败的消息，用于不区分类型地统计
+    pub fn is_delete_failed(&self) -> bool {
+        self.delete_failed
+    }
+
+    /// 类型不符时返回 None
+    pub fn downcast<T: Item>(&self) -> Option<ViewerMsg<T>> {
+        ErasedMsg::as_any(&*self.msg)
+            .downcast_ref::<ViewerMsg<T>>()
+            .cloned()
+    }
+}
+
+impl fmt::Debug for AnyViewerMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.msg.fmt(f)
+    }
+}
+
+impl<T: Item> From<ViewerMsg<T>> for AnyViewerMsg {
+    fn from(msg: ViewerMsg<T>) -> Self {
+        AnyViewerMsg {
+            tp: T::TYPE,
+            delete_failed: matches!(msg, ViewerMsg::DeleteFailed { .. }),
+            msg: Arc::new(msg),
+        }
+    }
+}
+
+impl<T: Item> From<ViewerMsg<T>> for main::Message {
+    fn from(msg: ViewerMsg<T>) -> Self {
+        main::Message::Viewer(msg.into())
+    }
+}
+
+/// 不区分具体类型的查看器，主界面只通过它显示、分派消息与传递数据
+///
+/// 新增一类项时实现 [`Item`] 与 [`Archived`]，并在主界面注册 `ItemViewer`
+pub trait Viewer: Debug {
+    fn tp(&self) -> Type;
+
+    /// 不属于该类型的消息被忽略
+    fn update(&mut self, msg: AnyViewerMsg) -> Action;
+
+    fn view<'a>(&'a self, parents: &'a HashMap<Parent, ParentInfo>) -> Element<'a, main::Message>;
+
+    fn is_deleting(&self) -> bool;
+
+    /// 演练模式拦截删除后恢复状态
+    fn stop_deleting(&mut self);
+
+    fn page_parents(&self) -> Vec<(Parent, Option<String>)>;
+
+    fn has_items(&self) -> bool;
+
+    /// 从获取或导入的数据中取出自己的一类
+    fn load(&mut self, data: &mut AggregatedData);
+
+    /// 导出时放入自己的一类
+    fn export(&self, data: &mut AggregatedData);
+
+    /// 导出导入这一类项的方式
+    fn codec(&self) -> Codec;
+}
+
+impl<T: Archived> Viewer for ItemViewer<T> {
+    fn tp(&self) -> Type {
+        T::TYPE
+    }
+
+    fn update(&mut self, msg: AnyViewerMsg) -> Action {
+        match msg.downcast::<T>() {
+            Some(msg) => ItemViewer::update(self, msg),
+            None => Action::None,
+        }
+    }
+
+    fn view<'a>(&'a self, parents: &'a HashMap<Parent, ParentInfo>) -> Element<'a, main::Message> {
+        ItemViewer::view(self, parents).map(main::Message::from)
+    }
+
+    fn is_deleting(&self) -> bool {
+        self.is_deleting
+    }
+
+    fn stop_deleting(&mut self) {
+        self.is_deleting = false;
+    }
+
+    fn page_parents(&self) -> Vec<(Parent, Option<String>)> {
+        ItemViewer::page_parents(self)
+    }
+
+    fn has_items(&self) -> bool {
+        self.items.is_some()
+    }
+
+    fn load(&mut self, data: &mut AggregatedData) {
+        ItemViewer::load(self, data.take::<T>());
+    }
+
+    fn export(&self, data: &mut AggregatedData) {
+        if let Some(list) = &self.items {
+            data.insert(list.to_map());
+        }
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::of::<T>()
+    }
+}
+
+impl<T: Item> Default for ItemViewer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Item> ItemViewer<T> {
+    pub fn new() -> Self {
+        ItemViewer {
+            items: None,
+            page: 0,
+            is_deleting: false,
+            is_fetching: true,
+            select_state: false,
+            filter: FilterBar::default(),
+            search: String::new(),
+            failed: HashMap::new(),
+        }
+    }
+
+    /// 显示获取或导入的数据
+    pub fn load(&mut self, items: HashMap<T::Key, T>) {
+        self.is_fetching = false;
+        self.page = 0;
+        self.search.clear();
+        self.failed.clear();
+        self.items = Some(ItemList::new(items));
+    }
+
+    /// 当前页的项所在的对象及获取时得到的链接，用于查询标题
+    pub fn page_parents(&self) -> Vec<(Parent, Option<String>)> {
+        let Some(list) = &self.items else {
+            return Vec::new();
+        };
+        list.page(self.page.min(list.pages() - 1))
+            .filter_map(|(_, i)| Some((i.parent()?, i.link().map(str::to_string))))
+            .collect()
+    }
+
+    /// `parents` 为已解析的标题与链接
+    pub fn view<'a>(
+        &'a self,
+        parents: &'a HashMap<Parent, ParentInfo>,
+    ) -> Element<'a, ViewerMsg<T>> {
+        if let Some(list) = &self.items {
+            let select_count = list.selected_count();
+            // 被搜索隐藏的选中项不会被删除
+            let hidden = list.hidden_selected_count();
+            let selected = if hidden == 0 {
+                format!("{} selected", select_count)
+            } else {
+                format!("{} selected ({} hidden)", select_count, hidden)
+            };
+            let head = if self.search.is_empty() {
+                text(format!("{} out of {} total", selected, list.len()))
+            } else {
+                text(format!(
+                    "{} out of {} total, {} found",
+                    selected,
+                    list.len(),
+                    list.shown_count()
+                ))
+            };
+            let tools = row![
+                text_input("search", &self.search)
+                    .size(14)
+                    .on_input(ViewerMsg::Search)
+                    .width(Length::Fill),
+                pick_list(SortBy::ALL, Some(list.sort()), ViewerMsg::SortChanged).text_size(14),
+                button(text(if list.is_descending() { "↓" } else { "↑" }).size(14))
+                    .padding(3)
+                    .on_press(ViewerMsg::OrderToggled),
+                toggler(list.is_grouped())
+                    .on_toggle(ViewerMsg::GroupToggled)
+                    .label("group")
+                    .text_size(14),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center);
+
+            // 只创建当前页的行
+            let pages = list.pages();
+            let page = self.page.min(pages - 1);
+            let mut cl = Column::new();
+            let mut group = None;
+            for (&key, i) in list.page(page) {
+                // 分组时在每组的第一项前显示组名
+                if list.is_grouped() && group != Some(i.parent()) {
+                    group = Some(i.parent());
+                    cl = cl.push(self.view_group(list, parents, i.parent(), i.link()));
+                }
+                cl = cl.push(
+                    Row::new()
+                        .push(
+                            checkbox(i.content(), i.is_selected())
+                                .text_shaping(text::Shaping::Advanced)
+                                .on_toggle_maybe(if !self.is_deleting {
+                                    Some(move |b| ViewerMsg::ChangeRemoveState(key, b))
+                                } else {
+                                    None
+                                })
+                                .width(Length::Fill),
+                        )
+                        .push_maybe(
+                            i.parent()
+                                .filter(|_| !list.is_grouped())
+                                .map(|p| view_parent::<T>(parents, p, i.link(), 12)),
+                        )
+                        .push_maybe(self.failed.get(&key).map(|e| {
+                            tooltip(
+                                text("failed")
+                                    .size(12)
+                                    .color(iced::Color::from_rgb(1.0, 0.0, 0.0)),
+                                text(e.clone()),
+                                tooltip::Position::FollowCursor,
+                            )
+                        }))
+                        .push(text(i.kind()).size(12))
+                        .push(text(i.time().map(format_time).unwrap_or_default()).size(12))
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                );
+            }
+            let cl = cl.padding([0, 15]);
+            let items = center(scrollable(cl).height(Length::Fill).width(Length::Fill));
+
+            let control = row![
+                if self.select_state {
+                    button("select all")
+                        .on_press_maybe((!self.is_deleting).then_some(ViewerMsg::SelectAll))
+                } else {
+                    button("deselect all")
+                        .on_press_maybe((!self.is_deleting).then_some(ViewerMsg::DeselectAll))
+                },
+                Space::with_width(Length::Fill),
+                button("<").on_press_maybe(page.checked_sub(1).map(ViewerMsg::PageChanged)),
+                text(format!("{} / {}", page + 1, pages)),
+                button(">")
+                    .on_press_maybe((page + 1 < pages).then_some(ViewerMsg::PageChanged(page + 1))),
+                Space::with_width(Length::Fill),
+                if self.is_deleting {
+                    button("stop").on_press(ViewerMsg::StopDelete)
+                } else {
+                    button("remove").on_press_maybe(if select_count > hidden {
+                        Some(ViewerMsg::Delete)
+                    } else {
+                        None
+                    })
+                }
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .height(Length::Shrink);
+
+            let filter = self.filter.view(self.is_deleting).map(ViewerMsg::Filter);
+
+            let failed = (!self.failed.is_empty()).then(|| {
+                row![
+                    text(format!("{} failed", self.failed.len()))
+                        .color(iced::Color::from_rgb(1.0, 0.0, 0.0)),
+                    button("retry failed")
+                        .on_press_maybe((!self.is_deleting).then_some(ViewerMsg::RetryFailed)),
+                ]
+                .spacing(5)
+                .align_y(Alignment::Center)
+            });
+
+            center(
+                iced::widget::column![head, tools, filter, items]
+                    .push_maybe(failed)
+                    .push(control)
+                    .align_x(Alignment::Center)
+                    .spacing(10),
+            )
+            .padding([5, 20])
+            .into()
+        } else {
+            center(scrollable(
+                column![text(if self.is_fetching {
+                    "Fetching..."
+                } else {
+                    "None 😭"
+                })
+                .shaping(text::Shaping::Advanced)]
+                .align_x(Alignment::Center)
+                .spacing(4),
+            ))
+            .into()
+        }
+    }
+
+    /// 组名、组内符合搜索的项数，以及整组选中或取消选中
+    fn view_group<'a>(
+        &self,
+        list: &ItemList<T>,
+        parents: &HashMap<Parent, ParentInfo>,
+        group: Option<Parent>,
+        link: Option<&str>,
+    ) -> Element<'a, ViewerMsg<T>> {
+        let name: Element<'a, ViewerMsg<T>> = match group {
+            Some(g) => view_parent::<T>(parents, g, link, 14),
+            None => text("ungrouped").size(14).into(),
+        };
+        let select = |label, b| {
+            button(text(label).size(12))
+                .padding(2)
+                .on_press_maybe((!self.is_deleting).then_some(ViewerMsg::SelectGroup(group, b)))
+        };
+        row![
+            name,
+            text(format!("({})", list.group_len(&group))).size(14),
+            Space::with_width(Length::Fill),
+            select("select", true),
+            select("deselect", false),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center)
+        .into()
+    }
+
+    pub fn update(&mut self, msg: ViewerMsg<T>) -> Action {
+        match msg {
+            ViewerMsg::ChangeRemoveState(key, b) => {
+                self.items.as_mut().unwrap().set_selected(&key, b);
+            }
+            ViewerMsg::SelectAll => {
+                self.select_state = false;
+                self.items.as_mut().unwrap().select_shown(|_| Some(true));
+            }
+            ViewerMsg::DeselectAll => {
+                self.select_state = true;
+                self.items.as_mut().unwrap().select_shown(|_| Some(false));
+            }
+            ViewerMsg::SelectGroup(group, b) => {
+                self.items
+                    .as_mut()
+                    .unwrap()
+                    .select_shown(|e| (e.parent() == group).then_some(b));
+            }
+            ViewerMsg::Search(query) => {
+                self.items.as_mut().unwrap().search(&query);
+                self.search = query;
+                self.page = 0;
+            }
+            ViewerMsg::SortChanged(sort) => {
+                let list = self.items.as_mut().unwrap();
+                list.set_sort(sort, list.is_descending());
+                self.page = 0;
+            }
+            ViewerMsg::OrderToggled => {
+                let list = self.items.as_mut().unwrap();
+                list.set_sort(list.sort(), !list.is_descending());
+                self.page = 0;
+            }
+            ViewerMsg::GroupToggled(b) => {
+                self.items.as_mut().unwrap().set_grouped(b);
+                self.page = 0;
+            }
+            ViewerMsg::PageChanged(page) => {
+                self.page = page;
+            }
+            ViewerMsg::Open(url) => {
+                if let Err(e) = meta::open_in_browser(&url) {
+                    error!("Failed to open {}: {}", url, e);
+                }
+            }
+            ViewerMsg::Delete => {
+                self.is_deleting = true;
+                return Action::Delete {
+                    items: Box::new(self.items.as_ref().unwrap().selected()),
+                };
+            }
+            ViewerMsg::Deleted { key } => {
+                self.failed.remove(&key);
+                if let Some(msg) = self
+                    .items
+                    .as_mut()
+                    .and_then(|list| list.remove(&key))
+                    .and_then(|i| i.cascade())
+                {
+                    return Action::Run(Task::done(msg));
+                }
+            }
+            ViewerMsg::DeleteFailed { key, error } => {
+                self.failed.insert(key, error);
+            }
+            ViewerMsg::RetryFailed => {
+                // 只选中失败的项，清空搜索使其都被删除；
+                // 失败记录在删除成功时才移除，演练模式下保持不变
+                let list = self.items.as_mut().unwrap();
+                list.search("");
+                list.select_where(|key, _| Some(self.failed.contains_key(key)));
+                self.search.clear();
+                self.page = 0;
+                return self.update(ViewerMsg::Delete);
+            }
+            ViewerMsg::Filter(msg) => {
+                if let Some((filter, action)) = self.filter.update(msg) {
+                    self.items
+                        .as_mut()
+                        .unwrap()
+                        .select_where(|_, e| action.apply(filter.matches(e)));
+                }
+            }
+            ViewerMsg::StopDelete => {
+                return Action::SendtoChannel(ChannelMsg::StopDelete(T::TYPE));
+            }
+            ViewerMsg::AllDeleted => {
+                self.is_deleting = false;
+                if let Some(list) = &mut self.items {
+                    list.compact();
+                }
+            }
+        }
+        Action::None
+    }
+}
+
+/// 所在对象的标题，未解析时显示编号；有链接时可点击打开
+///
+/// 优先使用获取时得到的链接
+fn view_parent<'a, T: Item>(
+    parents: &HashMap<Parent, ParentInfo>,
+    parent: Parent,
+    link: Option<&str>,
+    size: u16,
+) -> Element<'a, ViewerMsg<T>> {
+    let info = parents.get(&parent);
+    let title = info.map_or_else(|| parent.to_string(), |i| i.title.clone());
+    let url = link
+        .map(str::to_string)
+        .or_else(|| info.map(|i| i.url.clone()))
+        .filter(|u| !u.is_empty())
+        .or_else(|| meta::default_url(parent));
+    button(text(title).size(size).shaping(text::Shaping::Advanced))
+        .style(button::text)
+        .padding(0)
+        .on_press_maybe(url.map(ViewerMsg::Open))
+        .into()
+}
@@ -18,6 +18,6 @@ pub enum Screen {
 
 impl Screen {
     pub fn new(aicu_state: Arc<AtomicBool>) -> Self {
-        Screen::WaitScanQRcode(QRCode::new(aicu_state).0)
+        Screen::WaitScanQRcode(QRCode::new(aicu_state))
     }
 }
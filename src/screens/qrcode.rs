@@ -1,5 +1,5 @@
 use crate::http::qr_code::QRdata;
-use crate::types::{ChannelMsg, Result};
+use crate::types::{ChannelMsg, Result, Session};
 use iced::{
     widget::{button, center, column, qr_code, row, text, toggler, Space},
     Alignment, Element, Length, Task,
@@ -25,30 +25,28 @@ pub enum Message {
     EntertoCookieInput,
     QRcodeGot(Result<QRdata>),
     QRcodeRefresh,
-    QRcodeState(Result<(u64, Option<String>)>),
+    QRcodeState(Result<(u64, Option<Session>)>),
 }
 
 pub enum Action {
     Run(Task<Message>),
     SendtoChannel(ChannelMsg),
     GetState(Arc<Mutex<QRdata>>),
-    Boot { csrf: String, aicu_state: bool },
+    Boot { session: Session, aicu_state: bool },
     EnterCookie,
     None,
 }
 
 impl QRCode {
-    pub fn new(aicu_state: Arc<AtomicBool>) -> (Self, Task<Message>) {
-        (
-            QRCode {
-                qr_data: None,
-                qr_code: None,
-                qr_code_state: None,
-                aicu_state,
-                error: None,
-            },
-            Task::perform(QRdata::request_qrcode(), Message::QRcodeGot),
-        )
+    /// 二维码由 [`QRdata::request_qrcode`] 获取后以 [`Message::QRcodeGot`] 传入
+    pub fn new(aicu_state: Arc<AtomicBool>) -> Self {
+        QRCode {
+            qr_data: None,
+            qr_code: None,
+            qr_code_state: None,
+            aicu_state,
+            error: None,
+        }
     }
 
     pub fn view(&self) -> Element<Message> {
@@ -110,7 +108,7 @@ impl QRCode {
                     self.error = None;
                     if v.0 == 0 {
                         return Action::Boot {
-                            csrf: v.1.unwrap(),
+                            session: v.1.unwrap(),
                             aicu_state: self.aicu_state.load(Ordering::SeqCst),
                         };
                     }
@@ -0,0 +1,346 @@
+//! 本地数据目录、获取进度与保存的账号的持久化
+
+use crate::http::meta::ParentInfo;
+use crate::types::{Account, Error, FetchProgressState, Parent, Result, Session};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::Rng;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 可用该环境变量指定数据目录，如测试时使用临时目录
+pub const DATA_DIR_ENV: &str = "BILIBILI_COMMENT_CLEANING_DATA_DIR";
+
+/// 本地数据目录，如 `~/.local/share/bilibili-comment-cleaning`
+///
+/// 各存储另有 `open_in` 等接受目录的构造函数，ApiService 也可用 `with_data_dir` 指定
+pub fn data_dir() -> Result<PathBuf> {
+    let dir = match std::env::var_os(DATA_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("bilibili-comment-cleaning"),
+    };
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// 先写入临时文件再重命名，避免写入中途崩溃留下损坏的文件
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// 每个账号一个进度文件，中断或关闭后可从同一游标继续获取
+#[derive(Debug, Clone)]
+pub struct ProgressStore {
+    path: PathBuf,
+}
+
+impl ProgressStore {
+    /// `dir` 下的 `progress/{uid}.json`
+    pub fn new(dir: &Path, uid: u64) -> Result<Self> {
+        let dir = dir.join("progress");
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            path: dir.join(format!("{uid}.json")),
+        })
+    }
+
+    pub fn load(&self) -> Option<FetchProgressState> {
+        let bytes = fs::read(&self.path).ok()?;
+        match serde_json::from_slice(&bytes) {
+            Ok(state) => {
+                info!("Loaded fetch progress from {}", self.path.display());
+                Some(state)
+            }
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable progress file {}: {}",
+                    self.path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, state: &FetchProgressState) {
+        match serde_json::to_vec(state) {
+            Ok(bytes) => self.write(&bytes),
+            Err(e) => error!("Failed to save fetch progress: {}", e),
+        }
+    }
+
+    fn write(&self, bytes: &[u8]) {
+        if let Err(e) = write_atomic(&self.path, bytes) {
+            error!("Failed to save fetch progress: {}", e);
+        }
+    }
+
+    /// 获取全部完成后删除进度文件
+    pub fn clear(&self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove progress file: {}", e);
+            }
+        }
+    }
+}
+
+/// 进度中包含已获取的全部数据，每页都写入时总写入量随页数平方增长，因此限制写入的频率
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 序列化后的进度，序号越大越新
+#[derive(Debug)]
+pub struct Snapshot {
+    seq: u64,
+    bytes: Vec<u8>,
+}
+
+/// 按时间间隔保存获取进度
+///
+/// 持有进度的锁时用 [`Checkpointer::snapshot`] 序列化，释放锁后再用 [`Checkpointer::write`] 写入，其他来源不必等待磁盘
+#[derive(Debug)]
+pub struct Checkpointer {
+    store: Option<ProgressStore>,
+    interval: Duration,
+    /// 上次生成快照的时间及其序号
+    last: Mutex<(Instant, u64)>,
+    /// 已写入的快照的序号，较旧的快照不会覆盖较新的
+    written: Mutex<u64>,
+}
+
+impl Checkpointer {
+    pub fn new(store: Option<ProgressStore>, interval: Duration) -> Self {
+        Self {
+            store,
+            interval,
+            last: Mutex::new((Instant::now(), 0)),
+            written: Mutex::new(0),
+        }
+    }
+
+    /// 距上次快照超过间隔或 `force` 时序列化 `state`
+    pub fn snapshot(&self, state: &FetchProgressState, force: bool) -> Option<Snapshot> {
+        self.store.as_ref()?;
+        let mut last = self.last.lock().unwrap();
+        if !force && last.0.elapsed() < self.interval {
+            return None;
+        }
+        let bytes = serde_json::to_vec(state)
+            .inspect_err(|e| error!("Failed to save fetch progress: {}", e))
+            .ok()?;
+        *last = (Instant::now(), last.1 + 1);
+        Some(Snapshot { seq: last.1, bytes })
+    }
+
+    pub fn write(&self, snapshot: Option<Snapshot>) {
+        let (Some(store), Some(snapshot)) = (&self.store, snapshot) else {
+            return;
+        };
+        let mut written = self.written.lock().unwrap();
+        if snapshot.seq > *written {
+            store.write(&snapshot.bytes);
+            *written = snapshot.seq;
+        }
+    }
+}
+
+/// 已解析的视频、动态、专栏的标题与链接，各账号共用
+#[derive(Debug, Clone)]
+pub struct ParentCache {
+    path: PathBuf,
+}
+
+impl ParentCache {
+    pub fn open() -> Result<Self> {
+        Self::open_in(&data_dir()?)
+    }
+
+    pub fn open_in(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            path: dir.join("parents.json"),
+        })
+    }
+
+    pub fn load(&self) -> HashMap<Parent, ParentInfo> {
+        let Ok(bytes) = fs::read(&self.path) else {
+            return HashMap::new();
+        };
+        match serde_json::from_slice::<Vec<(Parent, ParentInfo)>>(&bytes) {
+            Ok(entries) => entries.into_iter().collect(),
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable parent cache {}: {}",
+                    self.path.display(),
+                    e
+                );
+                HashMap::new()
+            }
+        }
+    }
+
+    pub fn save(&self, parents: &HashMap<Parent, ParentInfo>) {
+        // JSON 的键只能是字符串，按列表保存
+        let entries: Vec<_> = parents.iter().collect();
+        let res = serde_json::to_vec(&entries)
+            .map_err(Into::into)
+            .and_then(|bytes| write_atomic(&self.path, &bytes));
+        if let Err(e) = res {
+            error!("Failed to save parent cache: {}", e);
+        }
+    }
+}
+
+/// 用于加密保存的账号的口令，未设置时不保存账号
+pub const PASSPHRASE_ENV: &str = "BILIBILI_COMMENT_CLEANING_PASSPHRASE";
+
+/// 保存的账号（含 Cookie），最近使用的在前
+///
+/// 内容用由口令派生的密钥加密，盐与 nonce 随密文保存，密钥不落盘；在 Unix 上仅当前用户可读写
+#[derive(Clone)]
+pub struct AccountStore {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    cipher: ChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for AccountStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccountStore")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccountStore {
+    /// 使用 [`PASSPHRASE_ENV`] 中的口令
+    pub fn open() -> Result<Self> {
+        let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| Error::NoPassphrase)?;
+        Self::open_in(&data_dir()?, &passphrase)
+    }
+
+    /// `dir` 下的 `accounts.enc`；已有的账号文件沿用其中的盐，否则生成新的盐
+    pub fn open_in(dir: &Path, passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(Error::NoPassphrase);
+        }
+        let path = dir.join("accounts.enc");
+        let salt = match fs::read(&path) {
+            Ok(bytes) if bytes.len() >= SALT_LEN => bytes[..SALT_LEN].try_into().unwrap(),
+            _ => {
+                let mut salt = [0; SALT_LEN];
+                rand::rng().fill(&mut salt);
+                salt
+            }
+        };
+        let mut key = [0; KEY_LEN];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, KDF_ROUNDS, &mut key);
+        Ok(Self {
+            path,
+            salt,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// 没有保存过账号时为空；口令错误或文件损坏时返回错误，此时 [`AccountStore::save`] 也会拒绝写入
+    pub fn load(&self) -> Result<Vec<Account>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        bytes
+            .strip_prefix(&self.salt[..])
+            .ok_or(Error::SessionCryptoError)
+            .and_then(|sealed| decrypt(&self.cipher, sealed))
+            .and_then(|plain| Ok(serde_json::from_slice(&plain)?))
+            .map_err(|e| {
+                warn!("Can't read accounts file {}: {}", self.path.display(), e);
+                Error::WrongPassphrase
+            })
+    }
+
+    /// 已有的文件无法用当前口令解密时不覆盖，避免丢失其中的账号
+    pub fn save(&self, accounts: &[Account]) -> Result<()> {
+        self.load()?;
+        let plain = serde_json::to_vec(accounts)?;
+        let sealed = encrypt(&self.cipher, &plain)?;
+        write_private(&self.path, &[&self.salt[..], &sealed].concat())
+    }
+
+    /// Cookie 刷新后更新对应的账号
+    pub fn update_session(&self, session: &Session) {
+        let Some(uid) = session.uid() else {
+            return;
+        };
+        let res =
+            self.load().and_then(
+                |mut accounts| match accounts.iter_mut().find(|a| a.uid == uid) {
+                    Some(account) => {
+                        account.cookie = session.cookie.clone();
+                        account.refresh_token = session.refresh_token.clone();
+                        self.save(&accounts)
+                    }
+                    None => Ok(()),
+                },
+            );
+        if let Err(e) = res {
+            error!("Failed to save refreshed session: {}", e);
+        }
+    }
+}
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const KDF_ROUNDS: u32 = 100_000;
+
+/// nonce 与密文拼接保存
+fn encrypt(cipher: &ChaCha20Poly1305, plain: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0; NONCE_LEN];
+    rand::rng().fill(&mut nonce);
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| Error::SessionCryptoError)?;
+    Ok([&nonce[..], &sealed].concat())
+}
+
+fn decrypt(cipher: &ChaCha20Poly1305, bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() < NONCE_LEN {
+        return Err(Error::SessionCryptoError);
+    }
+    let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| Error::SessionCryptoError)
+}
+
+/// 与 [`write_atomic`] 相同，但临时文件创建时即仅当前用户可读写
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let tmp = path.with_extension("tmp");
+    // 残留的临时文件可能权限过宽，删除后重新创建
+    let _ = fs::remove_file(&tmp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -1,15 +1,25 @@
 use super::screens::*;
-use crate::http::comment::Comment;
-use crate::http::danmu::Danmu;
-use crate::http::notify::Notify;
+use crate::dry_run::Summary;
+use crate::filter::Filterable;
+use crate::http::api_service::ApiService;
+use crate::http::cookie::CookieJar;
+use crate::http::notify::{Feed, NotifyKey};
+use crate::http::source::SourceProgress;
+use crate::retry::RetryPolicy;
 use crate::screens::main;
-use crate::screens::main::comment_viewer::CvMsg;
-use crate::screens::main::danmu_viewer::DvMsg;
-use crate::screens::main::notify_viewer::NvMsg;
+use crate::screens::main::item_viewer::ViewerMsg;
+use crate::storage::AccountStore;
+use crate::Type;
+use iced::futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
 use std::num::ParseIntError;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use tokio::sync::{mpsc::Sender, Mutex};
+use tokio::sync::mpsc::Sender;
 use tracing::error;
 
 #[derive(Debug, Clone)]
@@ -20,51 +30,359 @@ pub enum Message {
     Cookie(cookie::Message),
     QRCode(qrcode::Message),
     Main(main::Message),
+
+    /// 启动时验证保存的登录，得到 uid
+    SessionChecked(Result<u64>),
+    /// 登录后获取到的 (uid, 昵称)
+    AccountLoaded(Result<(u64, String)>),
+    SwitchAccount(u64),
+    AddAccount,
+    /// 界面中输入保存账号的口令
+    PassphraseChanged(String),
+    UnlockAccounts,
+    AccountsUnlocked(Result<(AccountStore, Vec<Account>)>),
 }
 
-impl From<CvMsg> for Message {
-    fn from(value: CvMsg) -> Self {
-        Message::Main(main::Message::CommentMsg(value))
+impl<T: Item> From<ViewerMsg<T>> for Message {
+    fn from(value: ViewerMsg<T>) -> Self {
+        Message::Main(value.into())
     }
 }
-impl From<NvMsg> for Message {
-    fn from(value: NvMsg) -> Self {
-        Message::Main(main::Message::NotifyMsg(value))
+
+pub enum ChannelMsg {
+    /// 删除一类项中选中的项
+    Delete(Arc<ApiService>, Box<dyn Deletable>, RetryPolicy),
+    StopDelete(Type),
+}
+
+/// 可在界面中查看、筛选并删除的一类项，实现后即可使用通用的查看器与删除流程
+pub trait Item:
+    RemoveAble + Filterable + Clone + Debug + Serialize + Send + Sync + 'static
+{
+    const TYPE: Type;
+
+    fn is_selected(&self) -> bool;
+
+    fn set_selected(&mut self, selected: bool);
+
+    /// 删除后还需通知其他查看器的消息，如移除随之删除的通知
+    fn cascade(&self) -> Option<main::Message> {
+        None
+    }
+
+    /// 数据来源，导出时记录
+    fn origin(&self) -> Origin;
+
+    /// 类型，用于排序与显示
+    fn kind(&self) -> &'static str;
+
+    /// 所在的视频、动态等，用于分组
+    fn parent(&self) -> Option<Parent> {
+        None
+    }
+
+    /// 获取时得到的所在对象的链接
+    fn link(&self) -> Option<&str> {
+        None
     }
 }
-impl From<DvMsg> for Message {
-    fn from(value: DvMsg) -> Self {
-        Message::Main(main::Message::DanmuMsg(value))
+
+/// 数据来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// 收到的赞
+    Liked,
+    /// 收到的回复
+    Replyed,
+    /// 被At
+    Ated,
+    /// 系统通知
+    System,
+    /// 自己动态下的评论区
+    Dynamic,
+    /// aicu.cc
+    Aicu,
+}
+
+/// 评论、弹幕所在的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Parent {
+    /// 视频的 av 号
+    Video(u64),
+    Dynamic(u64),
+    /// 专栏的 cv 号
+    Article(u64),
+    /// 弹幕所在的分 P
+    Cid(u64),
+    /// 其他评论区
+    Other {
+        r#type: u8,
+        oid: u64,
+    },
+}
+
+impl Display for Parent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parent::Video(aid) => write!(f, "av{}", aid),
+            Parent::Dynamic(id) => write!(f, "dynamic {}", id),
+            Parent::Article(cv) => write!(f, "cv{}", cv),
+            Parent::Cid(cid) => write!(f, "cid {}", cid),
+            Parent::Other { r#type, oid } => write!(f, "type {} oid {}", r#type, oid),
+        }
     }
 }
 
-pub enum ChannelMsg {
-    DeleteComment(
-        Arc<super::http::api_service::ApiService>,
-        Arc<Mutex<HashMap<u64, Comment>>>,
-        f32,
-    ),
-    StopDeleteComment,
-    DeleteNotify(
-        Arc<super::http::api_service::ApiService>,
-        Arc<Mutex<HashMap<u64, Notify>>>,
-        f32,
-    ),
-    StopDeleteNotify,
-    DeleteDanmu(
-        Arc<super::http::api_service::ApiService>,
-        Arc<Mutex<HashMap<u64, Danmu>>>,
-        f32,
-    ),
-    StopDeleteDanmu,
+/// 不区分具体类型的待删除项，各类项共用同一种删除消息
+pub trait Deletable: Send {
+    fn tp(&self) -> Type;
+
+    fn delete(
+        self: Box<Self>,
+        flag: Arc<AtomicBool>,
+        output: iced::futures::channel::mpsc::Sender<Message>,
+        retry: RetryPolicy,
+        api: Arc<ApiService>,
+    ) -> BoxFuture<'static, ()>;
+
+    /// 只生成演练报告
+    fn dry_run(self: Box<Self>) -> BoxFuture<'static, Result<Summary>>;
+}
+
+/// 登录凭据：Cookie 与用于刷新 Cookie 的 refresh_token（仅扫码登录时获取得到）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Session {
+    pub cookie: String,
+    pub refresh_token: Option<String>,
+}
+
+impl Session {
+    /// Cookie 中的 DedeUserID
+    pub fn uid(&self) -> Option<u64> {
+        CookieJar::from_header(&self.cookie).uid()
+    }
+}
+
+/// 保存的账号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub uid: u64,
+    pub nickname: String,
+    pub cookie: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+impl Account {
+    pub fn session(&self) -> Session {
+        Session {
+            cookie: self.cookie.clone(),
+            refresh_token: self.refresh_token.clone(),
+        }
+    }
+}
+
+impl PartialEq for Account {
+    fn eq(&self, other: &Self) -> bool {
+        self.uid == other.uid
+    }
+}
+
+impl std::fmt::Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.nickname, self.uid)
+    }
+}
+
+/// 获取或导入的数据，按类型分别保存，各查看器取出自己的一类
+#[derive(Default, Clone)]
+pub struct AggregatedData(HashMap<Type, Box<dyn ErasedItems>>);
+
+trait ErasedItems: Send + Sync {
+    fn clone_box(&self) -> Box<dyn ErasedItems>;
+
+    fn len(&self) -> usize;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Item> ErasedItems for HashMap<T::Key, T> {
+    fn clone_box(&self) -> Box<dyn ErasedItems> {
+        Box::new(self.clone())
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Clone for Box<dyn ErasedItems> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl Debug for AggregatedData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(tp, items)| (tp, items.len())))
+            .finish()
+    }
+}
+
+impl AggregatedData {
+    /// 替换这一类项
+    pub fn insert<T: Item>(&mut self, items: HashMap<T::Key, T>) {
+        self.0.insert(T::TYPE, Box::new(items));
+    }
+
+    pub fn get<T: Item>(&self) -> Option<&HashMap<T::Key, T>> {
+        self.0.get(&T::TYPE)?.as_any().downcast_ref()
+    }
+
+    /// 这一类项，没有时插入空表
+    pub fn entry<T: Item>(&mut self) -> &mut HashMap<T::Key, T> {
+        self.0
+            .entry(T::TYPE)
+            .or_insert_with(|| Box::new(HashMap::<T::Key, T>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .expect("items are stored under their own type")
+    }
+
+    /// 取出这一类项，没有时为空
+    pub fn take<T: Item>(&mut self) -> HashMap<T::Key, T> {
+        self.0
+            .remove(&T::TYPE)
+            .and_then(|items| items.into_any().downcast().ok())
+            .map(|items| *items)
+            .unwrap_or_default()
+    }
 }
 
 pub trait RemoveAble {
-    fn remove(
+    /// 区分各项的键：评论、弹幕为 id，通知为 [`NotifyKey`]
+    type Key: ItemKey;
+
+    /// 删除分为几步，如评论与随之删除的通知为两步
+    fn steps(&self) -> usize {
+        1
+    }
+
+    /// 执行第 `step` 步（从 0 开始）；失败后只需重试这一步，已完成的步骤不会重复发送
+    fn remove_step(
         &self,
-        id: u64,
-        api: Arc<super::http::api_service::ApiService>,
-    ) -> impl std::future::Future<Output = Result<u64>> + Send;
+        key: Self::Key,
+        step: usize,
+        api: Arc<ApiService>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// 删除时会依次发送的请求，本身不发送任何请求
+    fn plan(&self, key: Self::Key, csrf: &str) -> Vec<PlannedRequest>;
+}
+
+/// 删除记录中保存为数字 id 与通知所在的列表
+pub trait ItemKey: Copy + Ord + Hash + Display + Debug + Send + Sync + 'static {
+    fn id(&self) -> u64;
+
+    fn feed(&self) -> Option<Feed>;
+
+    /// 由删除记录还原，旧记录缺少通知所在的列表时为 None
+    fn from_parts(id: u64, feed: Option<Feed>) -> Option<Self>;
+}
+
+impl ItemKey for u64 {
+    fn id(&self) -> u64 {
+        *self
+    }
+
+    fn feed(&self) -> Option<Feed> {
+        None
+    }
+
+    fn from_parts(id: u64, _: Option<Feed>) -> Option<Self> {
+        Some(id)
+    }
+}
+
+impl ItemKey for NotifyKey {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn feed(&self) -> Option<Feed> {
+        Some(self.feed)
+    }
+
+    fn from_parts(id: u64, feed: Option<Feed>) -> Option<Self> {
+        Some(NotifyKey::new(feed?, id))
+    }
+}
+
+/// 删除时会发送的一个 POST 请求
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "encoding", rename_all = "snake_case")]
+pub enum PlannedRequest {
+    Form {
+        url: String,
+        #[serde(serialize_with = "serialize_form")]
+        form: Vec<(&'static str, String)>,
+    },
+    Json {
+        url: String,
+        body: serde_json::Value,
+    },
+}
+
+impl PlannedRequest {
+    /// 刷新 Cookie 后用新的 csrf 替换请求中旧的 csrf
+    pub fn with_csrf(&self, old: &str, new: &str) -> Self {
+        let url = |url: &str| url.replace(&format!("csrf={old}"), &format!("csrf={new}"));
+        match self {
+            PlannedRequest::Form { url: u, form } => PlannedRequest::Form {
+                url: url(u),
+                form: form
+                    .iter()
+                    .map(|(k, v)| match *k {
+                        "csrf" | "csrf_token" => (*k, new.to_string()),
+                        _ => (*k, v.clone()),
+                    })
+                    .collect(),
+            },
+            PlannedRequest::Json { url: u, body } => {
+                let mut body = body.clone();
+                if let Some(csrf) = body.get_mut("csrf") {
+                    *csrf = new.into();
+                }
+                PlannedRequest::Json { url: url(u), body }
+            }
+        }
+    }
+}
+
+/// 报告中以对象而不是二元组数组显示表单
+fn serialize_form<S: serde::Serializer>(
+    form: &[(&'static str, String)],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(form.iter().map(|(k, v)| (k, v)))
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -78,92 +396,170 @@ pub enum Error {
     ParseIntError(Arc<ParseIntError>),
     #[error("Unrecognized URI: {0}")]
     UnrecognizedURI(Arc<String>),
-    #[error("Failed to delete comment, Response json: {0}")]
-    DeleteCommentError(Arc<serde_json::Value>),
-    #[error("Failed to delete danmu, Response json: {0}")]
-    DeleteDanmuError(Arc<serde_json::Value>),
-    #[error("Failed to delete notify, Response json: {0}")]
-    DeleteNotifyError(Arc<serde_json::Value>),
-    #[error("Failed to delete system notify, Response json: {0}")]
-    DeleteSystemNotifyError(Arc<serde_json::Value>),
-    #[error("Failed to create api service, cookie didn't contain bili_jct")]
-    CreateApiServiceError,
+    #[error("bilibili API error: {0}")]
+    Api(ApiFailure),
+    #[error("Failed to delete comment: {0}")]
+    DeleteCommentError(ApiFailure),
+    #[error("Failed to delete danmu: {0}")]
+    DeleteDanmuError(ApiFailure),
+    #[error("Failed to delete notify: {0}")]
+    DeleteNotifyError(ApiFailure),
+    #[error("Failed to delete system notify: {0}")]
+    DeleteSystemNotifyError(ApiFailure),
+    #[error("Invalid cookie: {0}")]
+    InvalidCookie(CookieError),
+    #[error("Failed to read browser cookies: {0}")]
+    BrowserCookieError(Arc<String>),
     #[error("Failed to get uid: {0}")]
     GetUIDError(Arc<reqwest::Error>),
+    #[error("IO error: {0}")]
+    IoError(Arc<std::io::Error>),
+    #[error("JSON error: {0}")]
+    JsonError(Arc<serde_json::Error>),
+    #[error("CSV error: {0}")]
+    CsvError(Arc<csv::Error>),
+    #[error("Failed to import: {0}")]
+    ImportError(Arc<String>),
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(Arc<String>),
+    #[error("Failed to refresh cookie: {0}")]
+    RefreshCookieError(Arc<String>),
+    #[error("Failed to encrypt or decrypt saved session")]
+    SessionCryptoError,
+    #[error("No passphrase for saved accounts")]
+    NoPassphrase,
+    #[error("Saved accounts can't be decrypted, wrong passphrase?")]
+    WrongPassphrase,
+    #[error("Fetching was interrupted {0} times, giving up")]
+    FetchInterrupted(u32),
 }
-impl From<reqwest::Error> for Error {
-    fn from(error: reqwest::Error) -> Self {
-        Self::RequestFailed(Arc::new(error))
+impl Error {
+    /// bilibili 返回的非 0 code
+    pub fn api_error(&self) -> Option<&BiliApiError> {
+        self.api_failure().map(|f| &f.error)
     }
-}
 
-impl From<ParseIntError> for Error {
-    fn from(error: ParseIntError) -> Self {
-        Self::ParseIntError(Arc::new(error))
+    /// bilibili 返回的原始 code 与 message
+    pub fn api_failure(&self) -> Option<&ApiFailure> {
+        match self {
+            Error::Api(f)
+            | Error::DeleteCommentError(f)
+            | Error::DeleteDanmuError(f)
+            | Error::DeleteNotifyError(f)
+            | Error::DeleteSystemNotifyError(f) => Some(f),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct LikedRecovery {
-    pub cursor_id: u64,
-    pub cursor_time: u64,
+/// bilibili 返回的非 0 响应：原始的 code 与 message，以及归类后的错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{error}")]
+pub struct ApiFailure {
+    pub code: i64,
+    pub message: String,
+    pub error: BiliApiError,
 }
 
-#[derive(Debug, Clone)]
-pub struct ReplyedRecovery {
-    pub cursor_id: u64,
-    pub cursor_time: u64,
+impl ApiFailure {
+    pub fn new(code: i64, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            error: BiliApiError::from_code(code, message),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct AtedRecovery {
-    pub cursor_id: u64,
-    pub cursor_time: u64,
+/// bilibili API 返回的非 0 code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BiliApiError {
+    #[error("not logged in")]
+    NotLoggedIn,
+    #[error("CSRF check failed")]
+    CsrfFailed,
+    #[error("rate limited ({code})")]
+    RateLimited { code: i64 },
+    #[error("no permission")]
+    NoPermission,
+    #[error("already deleted")]
+    AlreadyDeleted,
+    #[error("{message} ({code})")]
+    Other { code: i64, message: String },
 }
 
-#[derive(Debug, Clone)]
-pub struct SystemNotifyRecovery {
-    pub cursor: u64,
-    pub api_type: u8, // 0 or 1
-}
+impl BiliApiError {
+    pub fn from_code(code: i64, message: &str) -> Self {
+        match code {
+            -101 => Self::NotLoggedIn,
+            -111 => Self::CsrfFailed,
+            -412 | -509 | -799 => Self::RateLimited { code },
+            -403 => Self::NoPermission,
+            // -404: 啥都木有; 12022: 已经被删除了
+            -404 | 12022 => Self::AlreadyDeleted,
+            _ => Self::Other {
+                code,
+                message: message.to_string(),
+            },
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub struct AicuCommentRecovery {
-    pub uid: u64,
-    pub page: u32,
-    pub all_count: u64,
+    /// 登录失效，之后的请求都会失败
+    pub fn is_auth(&self) -> bool {
+        matches!(self, Self::NotLoggedIn | Self::CsrfFailed)
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct AicuDanmuRecovery {
-    pub uid: u64,
-    pub page: u32,
-    pub all_count: u64,
+/// Cookie 无法用于登录的原因
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CookieError {
+    #[error("cookie is empty")]
+    Empty,
+    #[error("missing {0}")]
+    Missing(String),
+    #[error("DedeUserID is not a number: {0:?}")]
+    InvalidUid(String),
+    #[error("{0} contains non-ASCII characters")]
+    NonAscii(String),
+    #[error("no Cookie header or -b option in the cURL command")]
+    NoCookieInCurl,
+    #[error("unrecognized JSON, expected an array of {{name, value}}: {0}")]
+    InvalidJson(String),
 }
 
-#[derive(Default, Debug, Clone)]
-pub struct FetchProgressState {
-    pub liked_data: (
-        HashMap<u64, Notify>,
-        HashMap<u64, Comment>,
-        HashMap<u64, Danmu>,
-    ),
-    pub liked_recovery: Option<LikedRecovery>,
-
-    pub replyed_data: (HashMap<u64, Notify>, HashMap<u64, Comment>),
-    pub replyed_recovery: Option<ReplyedRecovery>,
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Self::RequestFailed(Arc::new(error))
+    }
+}
 
-    pub ated_data: HashMap<u64, Notify>,
-    pub ated_recovery: Option<AtedRecovery>,
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Self::ParseIntError(Arc::new(error))
+    }
+}
 
-    pub system_notify_data: HashMap<u64, Notify>,
-    pub system_notify_recovery: Option<SystemNotifyRecovery>,
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(Arc::new(error))
+    }
+}
 
-    pub aicu_comment_data: HashMap<u64, Comment>,
-    pub aicu_comment_recovery: Option<AicuCommentRecovery>,
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::JsonError(Arc::new(error))
+    }
+}
 
-    pub aicu_danmu_data: HashMap<u64, Danmu>,
-    pub aicu_danmu_recovery: Option<AicuDanmuRecovery>,
+impl From<csv::Error> for Error {
+    fn from(error: csv::Error) -> Self {
+        Self::CsvError(Arc::new(error))
+    }
+}
 
-    pub aicu_enabled_last_run: bool,
+/// 各来源的获取进度，按来源的 id 保存
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct FetchProgressState {
+    pub sources: HashMap<String, SourceProgress>,
 }
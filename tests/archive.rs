@@ -0,0 +1,82 @@
+mod common;
+
+use bilibili_comment_cleaning::archive::{self, Codec};
+use bilibili_comment_cleaning::http::comment::Comment;
+use bilibili_comment_cleaning::http::danmu::Danmu;
+use bilibili_comment_cleaning::http::notify::{Feed, Notify, NotifyKey};
+use bilibili_comment_cleaning::types::AggregatedData;
+use common::temp_data_dir;
+use std::collections::HashMap;
+
+fn data() -> AggregatedData {
+    let mut data = AggregatedData::default();
+    data.insert(HashMap::from([(
+        1,
+        Comment {
+            oid: 10,
+            r#type: 1,
+            content: "comment".to_string(),
+            is_selected: true,
+            time: Some(100),
+            ..Default::default()
+        },
+    )]));
+    data.insert(HashMap::from([(
+        2,
+        Danmu {
+            content: "danmu".to_string(),
+            cid: 20,
+            is_selected: false,
+            notify_id: Some(3),
+            time: None,
+            uri: None,
+        },
+    )]));
+    data.insert(HashMap::from([(
+        NotifyKey::new(Feed::Replyed, 3),
+        Notify::new("notify".to_string(), 1, Some(300)),
+    )]));
+    data
+}
+
+fn codecs() -> [Codec; 3] {
+    [
+        Codec::of::<Comment>(),
+        Codec::of::<Danmu>(),
+        Codec::of::<Notify>(),
+    ]
+}
+
+#[test]
+fn exported_files_round_trip() {
+    let dir = temp_data_dir();
+    for name in ["archive.json", "archive.csv"] {
+        let path = dir.path().join(name);
+        archive::export(&path, &data(), &codecs()).unwrap();
+        let mut imported = archive::import(&path, &codecs()).unwrap();
+
+        let comments = imported.take::<Comment>();
+        assert_eq!((comments[&1].oid, comments[&1].time), (10, Some(100)));
+        let danmu = imported.take::<Danmu>();
+        assert_eq!((danmu[&2].cid, danmu[&2].is_selected), (20, false));
+        let notify = imported.take::<Notify>();
+        assert_eq!(notify[&NotifyKey::new(Feed::Replyed, 3)].content, "notify");
+    }
+}
+
+/// 只导入给出的类型：JSON 忽略其余字段，CSV 中的其余行报错
+#[test]
+fn only_given_kinds_are_imported() {
+    let dir = temp_data_dir();
+    let only_comments = [Codec::of::<Comment>()];
+
+    let path = dir.path().join("archive.json");
+    archive::export(&path, &data(), &codecs()).unwrap();
+    let mut imported = archive::import(&path, &only_comments).unwrap();
+    assert_eq!(imported.take::<Comment>().len(), 1);
+    assert!(imported.get::<Danmu>().is_none());
+
+    let path = dir.path().join("archive.csv");
+    archive::export(&path, &data(), &codecs()).unwrap();
+    assert!(archive::import(&path, &only_comments).is_err());
+}
@@ -0,0 +1,86 @@
+use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+use bilibili_comment_cleaning::browser::{read_cookie, Browser, Profile};
+use rusqlite::Connection;
+use std::path::PathBuf;
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "bilibili-comment-cleaning-test-{}-{name}.sqlite",
+        std::process::id()
+    ))
+}
+
+/// 按 Linux 上 Chromium 的方式加密，值前面带 32 字节的域名哈希
+fn encrypt_chromium(value: &str) -> Vec<u8> {
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+    let plain = [[0; 32].as_slice(), value.as_bytes()].concat();
+    let mut buf = vec![0; plain.len() + 16];
+    buf[..plain.len()].copy_from_slice(&plain);
+    let sealed = cbc::Encryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+        .encrypt_padded_mut::<Pkcs7>(&mut buf, plain.len())
+        .unwrap();
+    [b"v10".as_slice(), sealed].concat()
+}
+
+#[test]
+fn reads_firefox_cookies() {
+    let path = db_path("firefox");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE moz_cookies (host TEXT, name TEXT, value TEXT);
+         INSERT INTO moz_cookies VALUES
+           ('.bilibili.com', 'SESSDATA', 'session'),
+           ('.bilibili.com', 'bili_jct', 'csrf'),
+           ('www.bilibili.com', 'bili_jct', 'stale'),
+           ('.bilibili.com', 'DedeUserID', '42'),
+           ('.example.com', 'SESSDATA', 'other'),
+           ('.notbilibili.com', 'buvid3', 'other');",
+    )
+    .unwrap();
+    drop(conn);
+
+    let cookie = read_cookie(&Profile {
+        browser: Browser::Firefox,
+        name: "test".to_string(),
+        cookies: path.clone(),
+    })
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(cookie, "bili_jct=csrf; SESSDATA=session; DedeUserID=42");
+}
+
+#[test]
+fn decrypts_chromium_cookies() {
+    let path = db_path("chromium");
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE meta (key TEXT, value TEXT);
+         INSERT INTO meta VALUES ('version', '24');
+         CREATE TABLE cookies (host_key TEXT, name TEXT, value TEXT, encrypted_value BLOB);",
+    )
+    .unwrap();
+    for (name, value) in [
+        ("SESSDATA", "session"),
+        ("bili_jct", "csrf"),
+        ("DedeUserID", "42"),
+    ] {
+        conn.execute(
+            "INSERT INTO cookies VALUES ('.bilibili.com', ?1, '', ?2)",
+            (name, encrypt_chromium(value)),
+        )
+        .unwrap();
+    }
+    drop(conn);
+
+    let cookie = read_cookie(&Profile {
+        browser: Browser::Chromium,
+        name: "test".to_string(),
+        cookies: path.clone(),
+    })
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(cookie, "SESSDATA=session; bili_jct=csrf; DedeUserID=42");
+}
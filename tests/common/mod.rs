@@ -0,0 +1,260 @@
+//! 本地的 bilibili / aicu.cc 替身服务，返回 `tests/fixtures` 下的响应
+
+#![allow(dead_code)]
+
+use bilibili_comment_cleaning::http::api_service::{ApiService, BaseUrls};
+use bilibili_comment_cleaning::http::rate_limit::RateLimits;
+use bilibili_comment_cleaning::types::Session;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 包含 bili_jct 的测试 Cookie
+pub const COOKIE: &str = "SESSDATA=session; bili_jct=csrf-token; DedeUserID=42;";
+pub const CSRF: &str = "csrf-token";
+pub const REFRESH_TOKEN: &str = "refresh-token";
+/// 刷新 Cookie 后的 csrf
+pub const NEW_CSRF: &str = "new-csrf";
+
+/// 收到的请求
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub body: String,
+}
+
+impl Request {
+    /// 表单或查询字符串中的字段
+    pub fn param(&self, key: &str) -> Option<String> {
+        [&self.query, &self.body]
+            .into_iter()
+            .flat_map(|s| s.split('&'))
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.to_string())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    requests: Vec<Request>,
+    /// 按路径排队的响应，先于默认响应返回
+    queued: HashMap<String, VecDeque<Value>>,
+}
+
+pub struct MockServer {
+    pub url: String,
+    /// 本服务的 ApiService 写入删除记录、获取进度的目录
+    pub data_dir: TempDataDir,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle(stream, server_state.clone()));
+            }
+        });
+        Self {
+            url,
+            data_dir: temp_data_dir(),
+            state,
+        }
+    }
+
+    /// 指向本服务、不限速的 ApiService
+    pub fn api(&self) -> Arc<ApiService> {
+        self.api_with_rate_limits(RateLimits::unlimited())
+    }
+
+    /// 指向本服务、按 `limits` 限速的 ApiService
+    pub fn api_with_rate_limits(&self, limits: RateLimits) -> Arc<ApiService> {
+        Arc::new(
+            ApiService::new(COOKIE.to_string())
+                .unwrap()
+                .with_base_urls(BaseUrls::all(&self.url))
+                .with_rate_limits(limits)
+                .with_data_dir(self.data_dir.path().to_path_buf()),
+        )
+    }
+
+    /// 带有 refresh_token、可以刷新 Cookie 的 ApiService
+    pub fn api_with_refresh_token(&self) -> Arc<ApiService> {
+        let session = Session {
+            cookie: COOKIE.to_string(),
+            refresh_token: Some(REFRESH_TOKEN.to_string()),
+        };
+        Arc::new(
+            ApiService::from_session(session)
+                .unwrap()
+                .with_base_urls(BaseUrls::all(&self.url))
+                .with_rate_limits(RateLimits::unlimited())
+                .with_data_dir(self.data_dir.path().to_path_buf()),
+        )
+    }
+
+    /// 下一次请求 `path` 时返回 `body`
+    pub fn queue(&self, path: &str, body: Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .queued
+            .entry(path.to_string())
+            .or_default()
+            .push_back(body);
+    }
+
+    /// 发往 `path` 的请求
+    pub fn requests(&self, path: &str) -> Vec<Request> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+/// 测试期间的数据目录，离开作用域时删除
+pub struct TempDataDir(PathBuf);
+
+impl TempDataDir {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDataDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 每个测试独立的临时数据目录，不影响本机数据与其他测试
+pub fn temp_data_dir() -> TempDataDir {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "bilibili-comment-cleaning-test-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    TempDataDir(dir)
+}
+
+fn fixture(path: &str) -> Option<Value> {
+    let text = match path {
+        "/x/member/web/account" => include_str!("../fixtures/account.json"),
+        "/x/msgfeed/like" => include_str!("../fixtures/like.json"),
+        "/x/msgfeed/reply" => include_str!("../fixtures/reply.json"),
+        "/x/msgfeed/at" => include_str!("../fixtures/at.json"),
+        "/x/sys-msg/query_user_notify" => include_str!("../fixtures/query_user_notify.json"),
+        "/x/sys-msg/query_unified_notify" => {
+            return Some(json!({"code": 0, "data": {"system_notify_list": []}}))
+        }
+        "/x/sys-msg/query_notify_list" => include_str!("../fixtures/query_notify_list.json"),
+        "/api/v3/search/getreply" => include_str!("../fixtures/getreply.json"),
+        "/api/v3/search/getvideodm" => include_str!("../fixtures/getvideodm.json"),
+        "/x/player/pagelist" => include_str!("../fixtures/pagelist.json"),
+        "/x/polymer/web-dynamic/v1/feed/space" => include_str!("../fixtures/space_dynamic.json"),
+        "/x/v2/reply" => include_str!("../fixtures/reply_list.json"),
+        "/x/v2/reply/reply" => include_str!("../fixtures/reply_reply.json"),
+        "/x/v2/reply/del"
+        | "/x/msgfeed/del"
+        | "/x/sys-msg/del_notify_list"
+        | "/x/passport-login/web/confirm/refresh" => include_str!("../fixtures/ok.json"),
+        "/x/passport-login/web/cookie/refresh" => {
+            return Some(
+                json!({"code": 0, "data": {"status": 0, "refresh_token": "new-refresh-token"}}),
+            )
+        }
+        p if p.starts_with("/correspond/1/") => {
+            return Some(Value::String(
+                r#"<html><div id="1-name">refresh-csrf</div></html>"#.to_string(),
+            ))
+        }
+        _ => return None,
+    };
+    Some(serde_json::from_str(text).unwrap())
+}
+
+async fn handle(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.unwrap_or(0) == 0 || header == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).await.is_err() {
+        return;
+    }
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(Request {
+            method,
+            path: path.to_string(),
+            query: query.to_string(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+        state
+            .queued
+            .get_mut(path)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| fixture(path))
+    };
+    let (status, body) = match response {
+        Some(Value::String(html)) => ("200 OK", html),
+        Some(v) => ("200 OK", v.to_string()),
+        None => ("404 Not Found", String::new()),
+    };
+    let set_cookie = if path == "/x/passport-login/web/cookie/refresh" {
+        format!("Set-Cookie: SESSDATA=new-session; Path=/\r\nSet-Cookie: bili_jct={NEW_CSRF}; Path=/\r\n")
+    } else {
+        String::new()
+    };
+
+    let mut stream = reader.into_inner();
+    let _ = stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n{set_cookie}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await;
+    let _ = stream.shutdown().await;
+}
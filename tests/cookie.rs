@@ -0,0 +1,91 @@
+use bilibili_comment_cleaning::http::cookie::{is_bilibili_host, CookieJar};
+use bilibili_comment_cleaning::types::CookieError;
+
+const HEADER: &str = "SESSDATA=abc%2C123; bili_jct=csrf; DedeUserID=42";
+
+fn assert_logged_in(jar: CookieJar) {
+    assert_eq!(jar.get("SESSDATA"), Some("abc%2C123"));
+    assert_eq!(jar.csrf(), "csrf");
+    assert_eq!(jar.uid(), Some(42));
+}
+
+#[test]
+fn parses_raw_header() {
+    assert_logged_in(CookieJar::parse(HEADER).unwrap());
+    assert_logged_in(CookieJar::parse(&format!("Cookie: {HEADER};")).unwrap());
+}
+
+#[test]
+fn parses_curl_command() {
+    let header = format!(
+        "curl 'https://api.bilibili.com/x/web-interface/nav' \\\n  -H 'accept: */*' \\\n  -H 'cookie: {HEADER}' \\\n  -H 'user-agent: Mozilla/5.0'"
+    );
+    assert_logged_in(CookieJar::parse(&header).unwrap());
+
+    let option = format!("curl \"https://www.bilibili.com/\" -b \"{HEADER}\"");
+    assert_logged_in(CookieJar::parse(&option).unwrap());
+
+    assert_eq!(
+        CookieJar::parse("curl 'https://www.bilibili.com/'"),
+        Err(CookieError::NoCookieInCurl)
+    );
+}
+
+#[test]
+fn parses_netscape_cookies_txt() {
+    let text = "# Netscape HTTP Cookie File\n\
+        .bilibili.com\tTRUE\t/\tFALSE\t1767225600\tSESSDATA\tabc%2C123\n\
+        .bilibili.com\tTRUE\t/\tFALSE\t1767225600\tbili_jct\tcsrf\n\
+        #HttpOnly_.bilibili.com\tTRUE\t/\tFALSE\t1767225600\tDedeUserID\t42\n\
+        .example.com\tTRUE\t/\tFALSE\t1767225600\tbili_jct\tother\n\
+        .evilbilibili.com\tTRUE\t/\tFALSE\t1767225600\tbili_jct\tevil\n";
+    assert_logged_in(CookieJar::parse(text).unwrap());
+}
+
+#[test]
+fn only_bilibili_domains_are_imported() {
+    assert!(is_bilibili_host("bilibili.com"));
+    assert!(is_bilibili_host(".bilibili.com"));
+    assert!(is_bilibili_host("www.BiliBili.com"));
+    assert!(!is_bilibili_host("notbilibili.com"));
+    assert!(!is_bilibili_host(".evilbilibili.com"));
+    assert!(!is_bilibili_host("bilibili.com.evil.com"));
+    assert!(!is_bilibili_host(""));
+
+    let json = r#"[
+        {"domain": ".bilibili.com", "name": "SESSDATA", "value": "abc%2C123"},
+        {"domain": ".bilibili.com", "name": "bili_jct", "value": "csrf"},
+        {"domain": ".bilibili.com", "name": "DedeUserID", "value": "42"},
+        {"domain": "notbilibili.com", "name": "bili_jct", "value": "evil"},
+        {"name": "DedeUserID", "value": "7"}
+    ]"#;
+    assert_logged_in(CookieJar::parse(json).unwrap());
+}
+
+#[test]
+fn parses_json_export() {
+    let json = r#"[
+        {"domain": ".bilibili.com", "name": "SESSDATA", "value": "abc%2C123", "httpOnly": true},
+        {"domain": ".bilibili.com", "name": "bili_jct", "value": "csrf"},
+        {"domain": ".bilibili.com", "name": "DedeUserID", "value": "42"}
+    ]"#;
+    assert_logged_in(CookieJar::parse(json).unwrap());
+}
+
+#[test]
+fn reports_what_is_wrong() {
+    assert_eq!(CookieJar::parse("  "), Err(CookieError::Empty));
+    // 最后一个字段没有分号
+    assert_eq!(
+        CookieJar::parse("SESSDATA=abc; bili_jct=csrf"),
+        Err(CookieError::Missing("DedeUserID".to_string()))
+    );
+    assert_eq!(
+        CookieJar::parse("SESSDATA=abc; bili_jct=csrf; DedeUserID=me"),
+        Err(CookieError::InvalidUid("me".to_string()))
+    );
+    assert!(matches!(
+        CookieJar::parse("[{\"name\": 1}]"),
+        Err(CookieError::InvalidJson(_))
+    ));
+}
@@ -0,0 +1,128 @@
+use bilibili_comment_cleaning::http::comment::Comment;
+use bilibili_comment_cleaning::screens::main::item_list::{ItemList, SortBy, PAGE_SIZE};
+use bilibili_comment_cleaning::types::{Item, Parent};
+use std::collections::HashMap;
+
+fn comment(time: Option<u64>) -> Comment {
+    Comment {
+        content: "comment".to_string(),
+        is_selected: true,
+        time,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn list_is_newest_first_and_counts_selection() {
+    let mut list = ItemList::new(HashMap::from([
+        (1, comment(Some(100))),
+        (2, comment(None)),
+        (3, comment(Some(300))),
+    ]));
+    let keys: Vec<_> = list.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, [3, 1, 2]);
+    assert_eq!(list.selected_count(), 3);
+
+    list.set_selected(&1, false);
+    list.set_selected(&1, false);
+    assert_eq!(list.selected_count(), 2);
+    list.select_where(|key, _| Some(*key == 2));
+    assert_eq!(list.selected_count(), 1);
+
+    assert!(list.remove(&2).is_some());
+    assert!(list.remove(&2).is_none());
+    assert_eq!(list.selected_count(), 0);
+    assert_eq!(list.len(), 2);
+}
+
+#[test]
+fn removed_items_leave_holes_until_compacted() {
+    let items = (0..PAGE_SIZE as u64 * 10)
+        .map(|id| (id, comment(Some(id))))
+        .collect();
+    let mut list = ItemList::new(items);
+    assert_eq!(list.pages(), 10);
+
+    // 删除较新的九页，整理前剩下的项不换页
+    for id in PAGE_SIZE as u64..PAGE_SIZE as u64 * 10 {
+        list.remove(&id);
+    }
+    assert_eq!(list.len(), PAGE_SIZE);
+    assert_eq!(list.selected_count(), PAGE_SIZE);
+    assert!(list.page(0).next().is_none());
+    assert_eq!(list.page(9).count(), PAGE_SIZE);
+
+    list.compact();
+    assert_eq!(list.pages(), 1);
+    let page: Vec<_> = list.page(0).map(|(k, _)| *k).collect();
+    assert_eq!(page.len(), PAGE_SIZE);
+    assert_eq!(page[0], PAGE_SIZE as u64 - 1);
+    assert!(list.get(&0).is_some());
+    assert!(list.page(1).next().is_none());
+}
+
+#[test]
+fn search_sort_and_select_group() {
+    let video = |oid, content: &str| Comment {
+        oid,
+        r#type: 1,
+        content: content.to_string(),
+        ..Default::default()
+    };
+    let mut list = ItemList::new(HashMap::from([
+        (1, video(10, "Hello")),
+        (2, video(20, "hello world")),
+        (3, video(10, "bye")),
+    ]));
+    let keys = |list: &ItemList<Comment>| list.page(0).map(|(k, _)| *k).collect::<Vec<_>>();
+
+    list.search("HELLO");
+    assert_eq!(list.shown_count(), 2);
+    assert_eq!(keys(&list), [1, 2]);
+    list.search("");
+
+    list.set_sort(SortBy::Length, true);
+    assert_eq!(keys(&list), [2, 1, 3]);
+    list.set_grouped(true);
+    assert_eq!(keys(&list), [1, 3, 2]);
+    assert_eq!(list.group_len(&Some(Parent::Video(10))), 2);
+
+    list.select_shown(|c| (c.parent() == Some(Parent::Video(10))).then_some(true));
+    let selected: Vec<_> = list.selected().into_iter().map(|(k, _)| k).collect();
+    assert_eq!(selected, [1, 3]);
+    assert_eq!(list.selected_count(), 2);
+}
+
+/// 被搜索隐藏的选中项计入隐藏数，不会被删除
+#[test]
+fn hidden_selected_items_are_not_deleted() {
+    let comment = |content: &str| Comment {
+        content: content.to_string(),
+        is_selected: true,
+        ..Default::default()
+    };
+    let mut list = ItemList::new(HashMap::from([
+        (1, comment("keep")),
+        (2, comment("remove me")),
+        (3, comment("remove too")),
+    ]));
+
+    list.search("remove");
+    assert_eq!(list.selected_count(), 3);
+    assert_eq!(list.hidden_selected_count(), 1);
+    let mut selected: Vec<_> = list.selected().into_iter().map(|(k, _)| k).collect();
+    selected.sort();
+    assert_eq!(selected, [2, 3]);
+
+    list.set_selected(&1, false);
+    list.set_selected(&2, false);
+    assert_eq!(list.hidden_selected_count(), 0);
+    list.remove(&3);
+    assert_eq!(
+        (list.selected_count(), list.hidden_selected_count()),
+        (0, 0)
+    );
+
+    list.search("");
+    assert!(list.selected().is_empty());
+}
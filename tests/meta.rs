@@ -0,0 +1,99 @@
+mod common;
+
+use bilibili_comment_cleaning::http::meta::{is_allowed_url, resolve, ParentInfo};
+use bilibili_comment_cleaning::storage::ParentCache;
+use bilibili_comment_cleaning::types::Parent;
+use common::{temp_data_dir, MockServer};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn resolves_titles_and_links() {
+    let server = MockServer::start().await;
+    let api = server.api();
+
+    server.queue(
+        "/x/web-interface/view",
+        json!({"code": 0, "data": {"title": "番剧", "redirect_url": "https://www.bilibili.com/bangumi/play/ep1"}}),
+    );
+    let info = resolve(&api, Parent::Video(3001), None).await.unwrap();
+    assert_eq!(
+        info,
+        Some(ParentInfo {
+            title: "番剧".to_string(),
+            url: "https://www.bilibili.com/bangumi/play/ep1".to_string(),
+        })
+    );
+
+    // 弹幕通过通知链接中的 bv 号查询
+    server.queue(
+        "/x/web-interface/view",
+        json!({"code": 0, "data": {"title": "video"}}),
+    );
+    let uri = "https://www.bilibili.com/video/BV1xx411c7mE";
+    let info = resolve(&api, Parent::Cid(4002), Some(uri)).await.unwrap();
+    assert_eq!(info.unwrap().url, uri);
+    let requests = server.requests("/x/web-interface/view");
+    assert_eq!(requests[0].param("aid").as_deref(), Some("3001"));
+    assert_eq!(requests[1].param("bvid").as_deref(), Some("BV1xx411c7mE"));
+
+    server.queue(
+        "/x/polymer/web-dynamic/v1/detail",
+        json!({"code": 0, "data": {"item": {"modules": {"module_dynamic": {
+            "desc": {"text": "一二三四五六七八九十一二三四五六七八九十一二三四五六七八九十一二三\n第二行"},
+            "major": null
+        }}}}}),
+    );
+    let info = resolve(&api, Parent::Dynamic(5), Some("https://t.bilibili.com/6"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(info.title.chars().count(), 31);
+    assert!(info.title.ends_with('…'));
+    assert_eq!(info.url, "https://t.bilibili.com/6");
+    let requests = server.requests("/x/polymer/web-dynamic/v1/detail");
+    assert_eq!(requests[0].param("id").as_deref(), Some("6"));
+
+    // 无法查询的类型不发送请求
+    let other = Parent::Other { r#type: 22, oid: 1 };
+    assert_eq!(resolve(&api, other, None).await.unwrap(), None);
+    assert_eq!(resolve(&api, Parent::Cid(1), None).await.unwrap(), None);
+    assert_eq!(server.requests("/x/web-interface/view").len(), 2);
+}
+
+#[test]
+fn parent_cache_round_trips() {
+    let dir = temp_data_dir();
+    let cache = ParentCache::open_in(dir.path()).unwrap();
+    let parents = HashMap::from([
+        (
+            Parent::Article(7),
+            ParentInfo {
+                title: "article".to_string(),
+                url: "https://www.bilibili.com/read/cv7".to_string(),
+            },
+        ),
+        (
+            Parent::Other { r#type: 22, oid: 1 },
+            ParentInfo {
+                title: "other".to_string(),
+                url: String::new(),
+            },
+        ),
+    ]);
+    cache.save(&parents);
+    assert_eq!(cache.load(), parents);
+}
+
+#[test]
+fn only_bilibili_links_are_opened() {
+    assert!(is_allowed_url("https://www.bilibili.com/video/av1"));
+    assert!(is_allowed_url("https://t.bilibili.com/6"));
+    assert!(is_allowed_url("https://bilibili.com/"));
+    assert!(is_allowed_url("https://b23.tv/abc"));
+    assert!(!is_allowed_url("http://www.bilibili.com/video/av1"));
+    assert!(!is_allowed_url("https://notbilibili.com/"));
+    assert!(!is_allowed_url("https://www.bilibili.com.evil.com/"));
+    assert!(!is_allowed_url("file:///etc/passwd"));
+    assert!(!is_allowed_url("--help"));
+}
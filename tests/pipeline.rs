@@ -0,0 +1,350 @@
+mod common;
+
+use bilibili_comment_cleaning::http::api_service::ApiService;
+use bilibili_comment_cleaning::http::comment::Comment;
+use bilibili_comment_cleaning::http::danmu::Danmu;
+use bilibili_comment_cleaning::http::notify::{Feed, Notify, NotifyKey};
+use bilibili_comment_cleaning::http::source::{self, Registry};
+use bilibili_comment_cleaning::journal::{Journal, Record};
+use bilibili_comment_cleaning::retry::RetryPolicy;
+use bilibili_comment_cleaning::screens::main;
+use bilibili_comment_cleaning::types::{FetchProgressState, Message};
+use bilibili_comment_cleaning::types::{Item, Origin};
+use bilibili_comment_cleaning::{handle_delete, Type, ViewerMsg};
+use common::{MockServer, CSRF, NEW_CSRF, REFRESH_TOKEN};
+use iced::futures::channel::mpsc;
+use iced::futures::StreamExt;
+use serde_json::json;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn no_backoff() -> RetryPolicy {
+    RetryPolicy {
+        backoff: 0.0,
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    }
+}
+
+/// 无穷大或超出范围的等待时间不会 panic
+#[test]
+fn extreme_backoff_is_clamped() {
+    for backoff in [f32::INFINITY, f32::MAX, f32::NAN] {
+        let policy = RetryPolicy {
+            backoff,
+            max_backoff: backoff,
+            ..RetryPolicy::default()
+        };
+        policy.delay(3);
+    }
+    let policy = RetryPolicy {
+        backoff: 1e30,
+        max_backoff: f32::INFINITY,
+        ..RetryPolicy::default()
+    };
+    assert_eq!(policy.delay(1), std::time::Duration::MAX);
+}
+
+fn comment(oid: u64) -> Comment {
+    Comment {
+        oid,
+        r#type: 1,
+        content: "comment".to_string(),
+        is_selected: true,
+        ..Default::default()
+    }
+}
+
+/// 删除给定评论，返回界面收到的消息
+async fn delete_comments(
+    api: Arc<ApiService>,
+    items: Vec<(u64, Comment)>,
+) -> Vec<ViewerMsg<Comment>> {
+    let (output, receiver) = mpsc::channel(100);
+    handle_delete(
+        Arc::new(AtomicBool::new(true)),
+        output,
+        items,
+        no_backoff(),
+        api,
+    )
+    .await;
+    receiver
+        .filter_map(|msg| async move {
+            match msg {
+                Message::Main(main::Message::Viewer(m)) => m.downcast::<Comment>(),
+                _ => None,
+            }
+        })
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn fetch_collects_every_source() {
+    let server = MockServer::start().await;
+
+    let (data, progress) = source::fetch(
+        server.api(),
+        Registry::default(),
+        FetchProgressState::default(),
+        |_, _| {},
+    )
+    .await
+    .unwrap();
+    assert!(progress.is_none());
+    let mut data = Arc::unwrap_or_clone(data.unwrap());
+    let (notify, comments, danmu) = (
+        data.take::<Notify>(),
+        data.take::<Comment>(),
+        data.take::<Danmu>(),
+    );
+
+    // 系统通知与收到的赞的 id 相同，两者都应保留
+    let mut keys: Vec<_> = notify.keys().copied().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        [
+            NotifyKey::new(Feed::Liked, 1001),
+            NotifyKey::new(Feed::Liked, 1002),
+            NotifyKey::new(Feed::Replyed, 1101),
+            NotifyKey::new(Feed::Ated, 1201),
+            NotifyKey::new(Feed::System, 1001),
+        ]
+    );
+
+    let mut ids: Vec<_> = comments.keys().copied().collect();
+    ids.sort();
+    assert_eq!(ids, [2001, 2101, 2201, 2401, 2402, 2403, 2404]);
+    assert_eq!(comments[&2001].notify_id, Some(1001));
+    assert_eq!(comments[&2101].content, "replied comment");
+    assert_eq!(comments[&2201].oid, 3004);
+    assert_eq!(comments[&2404].content, "folded reply");
+    assert_eq!((comments[&2401].oid, comments[&2401].r#type), (3101, 17));
+    assert_eq!(comments[&2401].origin(), Origin::Dynamic);
+    assert_eq!(
+        server.requests("/x/v2/reply/reply")[0]
+            .param("root")
+            .as_deref(),
+        Some("2401")
+    );
+
+    assert_eq!(danmu[&2002].cid, 4002);
+    assert_eq!(danmu[&2301].cid, 4005);
+    assert_eq!(danmu.len(), 2);
+}
+
+#[tokio::test]
+async fn delete_sends_one_request_per_comment() {
+    let server = MockServer::start().await;
+
+    let msgs = delete_comments(
+        server.api(),
+        vec![(5001, comment(6001)), (5002, comment(6002))],
+    )
+    .await;
+
+    let requests = server.requests("/x/v2/reply/del");
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|r| r.method == "POST"));
+    assert_eq!(requests[0].param("rpid").as_deref(), Some("5001"));
+    assert_eq!(requests[0].param("oid").as_deref(), Some("6001"));
+    assert_eq!(requests[0].param("csrf").as_deref(), Some(CSRF));
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5002 })));
+    assert!(matches!(msgs.last(), Some(ViewerMsg::AllDeleted)));
+}
+
+#[tokio::test]
+async fn notify_with_same_id_in_different_feeds_use_their_own_api() {
+    let server = MockServer::start().await;
+    let liked = NotifyKey::new(Feed::Liked, 7001);
+    let system = NotifyKey::new(Feed::System, 7001);
+    let mut system_notify = Notify::new("system".to_string(), 1, None);
+    system_notify.system_notify_api = Some(0);
+
+    let (output, receiver) = mpsc::channel(100);
+    handle_delete(
+        Arc::new(AtomicBool::new(true)),
+        output,
+        vec![
+            (liked, Notify::new("liked".to_string(), 0, None)),
+            (system, system_notify),
+        ],
+        no_backoff(),
+        server.api(),
+    )
+    .await;
+    let msgs: Vec<_> = receiver
+        .filter_map(|msg| async move {
+            match msg {
+                Message::Main(main::Message::Viewer(m)) => m.downcast::<Notify>(),
+                _ => None,
+            }
+        })
+        .collect()
+        .await;
+
+    let msgfeed = server.requests("/x/msgfeed/del");
+    assert_eq!(msgfeed.len(), 1);
+    assert_eq!(msgfeed[0].param("id").as_deref(), Some("7001"));
+    let sys = server.requests("/x/sys-msg/del_notify_list");
+    assert_eq!(sys.len(), 1);
+    assert!(sys[0].body.contains(r#""ids":[7001]"#));
+    for key in [liked, system] {
+        assert!(msgs
+            .iter()
+            .any(|m| matches!(m, ViewerMsg::Deleted { key: k } if *k == key)));
+    }
+}
+
+#[tokio::test]
+async fn rate_limited_delete_is_retried() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/v2/reply/del",
+        json!({"code": -412, "message": "请求被拦截"}),
+    );
+
+    let msgs = delete_comments(server.api(), vec![(5101, comment(6101))]).await;
+
+    assert_eq!(server.requests("/x/v2/reply/del").len(), 2);
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5101 })));
+}
+
+#[tokio::test]
+async fn only_the_failed_step_is_retried() {
+    let server = MockServer::start().await;
+    // 评论已删除，随之删除的通知遇到风控
+    server.queue(
+        "/x/msgfeed/del",
+        json!({"code": -412, "message": "请求被拦截"}),
+    );
+    let liked = Comment {
+        notify_id: Some(7101),
+        tp: Some(0),
+        ..comment(6151)
+    };
+
+    let msgs = delete_comments(server.api(), vec![(5151, liked)]).await;
+
+    assert_eq!(server.requests("/x/v2/reply/del").len(), 1);
+    let notify = server.requests("/x/msgfeed/del");
+    assert_eq!(notify.len(), 2);
+    assert_eq!(notify[1].param("id").as_deref(), Some("7101"));
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5151 })));
+}
+
+#[tokio::test]
+async fn permanent_failure_is_reported() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/v2/reply/del",
+        json!({"code": -403, "message": "访问权限不足"}),
+    );
+
+    let msgs = delete_comments(server.api(), vec![(5201, comment(6201))]).await;
+
+    assert_eq!(server.requests("/x/v2/reply/del").len(), 1);
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::DeleteFailed { key: 5201, .. })));
+    // 删除记录保留 bilibili 返回的原始 code 与 message
+    let journal =
+        std::fs::read_to_string(Journal::open_in(server.data_dir.path()).unwrap().path()).unwrap();
+    let record: Record = journal
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Record>(l).ok())
+        .find(|r| r.id == 5201)
+        .unwrap();
+    assert_eq!(record.code, Some(-403));
+    assert_eq!(record.message.as_deref(), Some("访问权限不足"));
+}
+
+#[tokio::test]
+async fn already_deleted_counts_as_deleted() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/v2/reply/del",
+        json!({"code": 12022, "message": "已经被删除了"}),
+    );
+
+    let msgs = delete_comments(server.api(), vec![(5301, comment(6301))]).await;
+
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5301 })));
+}
+
+#[tokio::test]
+async fn journal_only_skips_ids_deleted_by_the_same_account() {
+    let server = MockServer::start().await;
+    // 另一个账号删除过同一 id
+    Journal::open_in(server.data_dir.path())
+        .unwrap()
+        .append(&Record::new(
+            Type::Comment,
+            Some(1),
+            5501u64,
+            &comment(6501),
+            &Ok(5501),
+        ));
+
+    delete_comments(server.api(), vec![(5501, comment(6501))]).await;
+    assert_eq!(server.requests("/x/v2/reply/del").len(), 1);
+
+    // 本账号删除过的不再发送请求
+    let msgs = delete_comments(server.api(), vec![(5501, comment(6501))]).await;
+    assert_eq!(server.requests("/x/v2/reply/del").len(), 1);
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5501 })));
+}
+
+#[tokio::test]
+async fn expired_cookie_is_refreshed_and_delete_continues() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/v2/reply/del",
+        json!({"code": -101, "message": "账号未登录"}),
+    );
+    let api = server.api_with_refresh_token();
+
+    let msgs = delete_comments(api.clone(), vec![(5401, comment(6401))]).await;
+
+    let refresh = server.requests("/x/passport-login/web/cookie/refresh");
+    assert_eq!(refresh.len(), 1);
+    assert_eq!(
+        refresh[0].param("refresh_token").as_deref(),
+        Some(REFRESH_TOKEN)
+    );
+    assert_eq!(
+        refresh[0].param("refresh_csrf").as_deref(),
+        Some("refresh-csrf")
+    );
+    let confirm = server.requests("/x/passport-login/web/confirm/refresh");
+    assert_eq!(confirm[0].param("csrf").as_deref(), Some(NEW_CSRF));
+    assert_eq!(
+        confirm[0].param("refresh_token").as_deref(),
+        Some(REFRESH_TOKEN)
+    );
+
+    let requests = server.requests("/x/v2/reply/del");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].param("csrf").as_deref(), Some(NEW_CSRF));
+    assert_eq!(api.csrf(), NEW_CSRF);
+    assert!(api.session().cookie.contains("SESSDATA=new-session"));
+    assert_eq!(
+        api.session().refresh_token.as_deref(),
+        Some("new-refresh-token")
+    );
+    assert!(msgs
+        .iter()
+        .any(|m| matches!(m, ViewerMsg::Deleted { key: 5401 })));
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use bilibili_comment_cleaning::http::qr_code::QRdata;
+use common::MockServer;
+use serde_json::json;
+
+#[tokio::test]
+async fn qrcode_uses_configured_api() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/passport-login/web/qrcode/generate",
+        json!({"code": 0, "data": {"url": "https://account.bilibili.com/h5/account-h5/auth/scan-web?qrcode_key=k", "qrcode_key": "k"}}),
+    );
+    let data = QRdata::request_qrcode(server.api()).await.unwrap();
+    assert_eq!(data.key, "k");
+    assert_eq!(
+        server
+            .requests("/x/passport-login/web/qrcode/generate")
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn unexpected_bodies_are_errors() {
+    let server = MockServer::start().await;
+    server.queue(
+        "/x/passport-login/web/qrcode/generate",
+        json!({"code": 0, "data": {"url": 1}}),
+    );
+    assert!(QRdata::request_qrcode(server.api()).await.is_err());
+
+    server.queue("/x/member/web/account", json!({"code": 0, "data": {}}));
+    assert!(server.api().get_account().await.is_err());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use bilibili_comment_cleaning::http::rate_limit::{Limiter, Rate, RateLimits, TokenBucket};
+use bilibili_comment_cleaning::types::BiliApiError;
+use common::MockServer;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn bucket_spaces_requests_after_burst() {
+    let bucket = TokenBucket::new(Rate::new(20.0, 2));
+    let start = Instant::now();
+    for _ in 0..2 {
+        bucket.acquire().await;
+    }
+    // 容量内的请求不需要等待
+    assert!(start.elapsed() < Duration::from_millis(40));
+    for _ in 0..3 {
+        bucket.acquire().await;
+    }
+    assert!(start.elapsed() >= Duration::from_millis(140));
+}
+
+#[test]
+fn risk_control_slows_down_until_recovered() {
+    let bucket = TokenBucket::new(Rate::per_second(2.0));
+    bucket.observe(-412);
+    bucket.observe(-799);
+    assert_eq!(bucket.slowdown(), 4.0);
+    // 其他错误不影响速率
+    bucket.observe(-404);
+    assert_eq!(bucket.slowdown(), 4.0);
+    for _ in 0..100 {
+        bucket.observe(0);
+    }
+    assert_eq!(bucket.slowdown(), 1.0);
+}
+
+#[tokio::test]
+async fn api_service_slows_down_on_risk_control() {
+    let server = MockServer::start().await;
+    let api = server.api_with_rate_limits(RateLimits {
+        api: Some(Rate::new(20.0, 1)),
+        ..RateLimits::unlimited()
+    });
+    server.queue(
+        "/x/member/web/account",
+        json!({"code": -412, "message": "请求被拦截"}),
+    );
+    let err = api.get_uid().await.unwrap_err();
+    assert_eq!(
+        err.api_error(),
+        Some(&BiliApiError::RateLimited { code: -412 })
+    );
+
+    // 降速后每个请求约间隔 100ms，而不是 50ms
+    let start = Instant::now();
+    for _ in 0..3 {
+        assert_eq!(api.get_uid().await.unwrap(), 42);
+    }
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
+
+#[tokio::test]
+async fn delete_rate_can_be_changed_at_runtime() {
+    let limiter = Limiter::from(RateLimits {
+        delete: Some(Rate::every(3600.0)),
+        ..RateLimits::unlimited()
+    });
+    // 桶满时第一个请求立即发出，第二个需要等待
+    let bucket = limiter.delete().unwrap();
+    bucket.acquire().await;
+    limiter.set_delete(Some(Rate::per_second(1000.0)));
+    let start = Instant::now();
+    limiter.delete().unwrap().acquire().await;
+    assert!(start.elapsed() < Duration::from_secs(1));
+    limiter.set_delete(None);
+    assert!(limiter.delete().is_none());
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use bilibili_comment_cleaning::http::api_service::ApiService;
+use bilibili_comment_cleaning::http::comment::dynamic::DynamicComments;
+use bilibili_comment_cleaning::http::comment::Comment;
+use bilibili_comment_cleaning::http::source::{
+    self, decode_cursor, Cursor, Items, Page, Registry, Source,
+};
+use bilibili_comment_cleaning::types::{Error, FetchProgressState};
+use common::MockServer;
+use iced::futures::future::BoxFuture;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 共三页，每页一条评论，评论 id 为 base + 页码；fail_once 时第二页第一次获取失败
+struct Numbers {
+    id: &'static str,
+    base: u64,
+    fail_once: AtomicBool,
+}
+
+impl Numbers {
+    fn new(id: &'static str, base: u64, fail_once: bool) -> Self {
+        Self {
+            id,
+            base,
+            fail_once: AtomicBool::new(fail_once),
+        }
+    }
+}
+
+impl Source for Numbers {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        "Numbers"
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        _api: &'a Arc<ApiService>,
+        cursor: Option<Cursor>,
+    ) -> BoxFuture<'a, bilibili_comment_cleaning::types::Result<Page>> {
+        Box::pin(async move {
+            let page: u64 = decode_cursor(cursor)?.unwrap_or(1);
+            if page == 2 && self.fail_once.swap(false, Ordering::SeqCst) {
+                return Err(Error::UnrecognizedURI(Arc::new("page 2".to_string())));
+            }
+            let mut items = Items::default();
+            items.comments.insert(
+                self.base + page,
+                Comment {
+                    oid: page,
+                    ..Default::default()
+                },
+            );
+            if page == 3 {
+                Ok(Page::end(items))
+            } else {
+                Page::next(items, &(page + 1))
+            }
+        })
+    }
+}
+
+/// 被关闭时不应被调用
+struct Unused;
+
+impl Source for Unused {
+    fn id(&self) -> &'static str {
+        "unused"
+    }
+
+    fn name(&self) -> &'static str {
+        "Unused"
+    }
+
+    fn fetch_page<'a>(
+        &'a self,
+        _api: &'a Arc<ApiService>,
+        _cursor: Option<Cursor>,
+    ) -> BoxFuture<'a, bilibili_comment_cleaning::types::Result<Page>> {
+        unreachable!("disabled source was fetched")
+    }
+}
+
+/// 同一账号的进度保存在同一处，放在一个测试里按顺序进行
+#[tokio::test]
+async fn sources_resume_from_saved_cursor() {
+    let server = MockServer::start().await;
+    let mut sources = Registry::empty();
+    sources.register(Numbers::new("flaky", 0, true));
+    sources.register(Numbers::new("steady", 10, false));
+    sources.register(Unused);
+    sources.set_enabled("unused", false);
+
+    let mut reports = Vec::new();
+    let (data, progress) = source::fetch(
+        server.api(),
+        sources.clone(),
+        FetchProgressState::default(),
+        |id, status| reports.push((id, status)),
+    )
+    .await
+    .unwrap();
+    assert!(data.is_none());
+    let progress = progress.unwrap();
+    // 失败的来源不影响其余来源
+    assert_eq!(progress.sources["flaky"].pages, 1);
+    assert!(!progress.sources["flaky"].done);
+    assert_eq!(progress.sources["steady"].pages, 3);
+    assert!(progress.sources["steady"].done);
+    assert!(!progress.sources.contains_key("unused"));
+    assert!(reports.iter().any(|(id, s)| *id == "flaky" && s.failed));
+    assert!(!reports.iter().any(|(id, s)| *id == "steady" && s.failed));
+
+    let (data, progress) = source::fetch(server.api(), sources, progress, |_, _| {})
+        .await
+        .unwrap();
+    assert!(progress.is_none());
+    let comments = Arc::unwrap_or_clone(data.unwrap()).take::<Comment>();
+    let mut ids: Vec<_> = comments.keys().copied().collect();
+    ids.sort();
+    assert_eq!(ids, [1, 2, 3, 11, 12, 13]);
+}
+
+/// 楼中楼的每一页也是一步，中断后从游标继续，不会重新请求整个评论区
+#[tokio::test]
+async fn dynamic_sub_replies_are_resumable_steps() {
+    let server = MockServer::start().await;
+    let api = server.api();
+    let paths = [
+        "/x/polymer/web-dynamic/v1/feed/space",
+        "/x/v2/reply",
+        "/x/v2/reply/reply",
+    ];
+    let requests = || paths.map(|p| server.requests(p).len());
+
+    let mut cursor = None;
+    let mut comments = Vec::new();
+    let mut steps = Vec::new();
+    loop {
+        let page = DynamicComments.fetch_page(&api, cursor).await.unwrap();
+        comments.extend(page.items.comments.into_keys());
+        steps.push(requests());
+        cursor = page.next;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    assert_eq!(steps, [[1, 0, 0], [1, 1, 0], [1, 1, 1]]);
+    let requests = server.requests("/x/v2/reply/reply");
+    assert_eq!(requests[0].param("root").as_deref(), Some("2401"));
+    comments.sort();
+    comments.dedup();
+    assert_eq!(comments, [2401, 2402, 2403, 2404]);
+}
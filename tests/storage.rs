@@ -0,0 +1,72 @@
+mod common;
+
+use bilibili_comment_cleaning::http::source::SourceProgress;
+use bilibili_comment_cleaning::storage::{AccountStore, Checkpointer, ProgressStore};
+use bilibili_comment_cleaning::types::{Account, Error, FetchProgressState};
+use common::{temp_data_dir, COOKIE};
+use std::time::Duration;
+
+#[test]
+fn saved_accounts_are_encrypted() {
+    let dir = temp_data_dir();
+    let store = AccountStore::open_in(dir.path(), "passphrase").unwrap();
+    let accounts = vec![Account {
+        uid: 42,
+        nickname: "tester".to_string(),
+        cookie: COOKIE.to_string(),
+        refresh_token: None,
+    }];
+
+    store.save(&accounts).unwrap();
+
+    let raw = std::fs::read(dir.path().join("accounts.enc")).unwrap();
+    assert!(!String::from_utf8_lossy(&raw).contains("SESSDATA"));
+    let loaded = store.load().unwrap();
+    assert_eq!(loaded, accounts);
+    assert_eq!(loaded[0].cookie, COOKIE);
+
+    // 密钥不落盘，重新打开时由口令与文件中的盐派生
+    assert!(!dir.path().join("accounts.key").exists());
+    let reopened = AccountStore::open_in(dir.path(), "passphrase").unwrap();
+    assert_eq!(reopened.load().unwrap(), accounts);
+    let wrong = AccountStore::open_in(dir.path(), "wrong").unwrap();
+    // 口令错误时既不返回空列表，也不覆盖已保存的账号
+    assert!(matches!(wrong.load(), Err(Error::WrongPassphrase)));
+    assert!(wrong.save(&[]).is_err());
+    assert_eq!(reopened.load().unwrap(), accounts);
+    assert!(AccountStore::open_in(dir.path(), "").is_err());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.path().join("accounts.enc");
+        let mode = std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn checkpoints_are_throttled_and_never_go_back() {
+    let dir = temp_data_dir();
+    let store = ProgressStore::new(dir.path(), 7).unwrap();
+    let checkpoints = Checkpointer::new(Some(store.clone()), Duration::from_secs(3600));
+    let state = |pages| FetchProgressState {
+        sources: [(
+            "liked".to_string(),
+            SourceProgress {
+                pages,
+                ..Default::default()
+            },
+        )]
+        .into(),
+    };
+
+    assert!(checkpoints.snapshot(&state(1), false).is_none());
+    let older = checkpoints.snapshot(&state(2), true);
+    let newer = checkpoints.snapshot(&state(3), true);
+    checkpoints.write(newer);
+    // 先生成、后写入的旧快照被忽略
+    checkpoints.write(older);
+    assert_eq!(store.load().unwrap().sources["liked"].pages, 3);
+    store.clear();
+}